@@ -1,19 +1,34 @@
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
 CREATE TYPE STATUSADDR as ENUM ('Reserved', 'Unknown', 'Online', 'Offline', 'Reachable');
 CREATE TYPE ROLE AS ENUM ('Admin', 'Operator', 'Guest');
-CREATE TYPE STATUS_NETWORK AS ENUM ('Available', 'Used', 'Reserved');
+CREATE TYPE STATUS_NETWORK AS ENUM ('Available', 'Used', 'Reserved', 'Assigned');
 CREATE TYPE KIND_NETWORK AS ENUM ('Network', 'Pool');
+CREATE TYPE CUSTOM_FIELD_KIND AS ENUM ('String', 'Int', 'Bool', 'Enum', 'Date');
+CREATE TYPE CUSTOM_FIELD_TARGET AS ENUM ('Network', 'Addresses', 'Node', 'Vlan');
+CREATE TYPE CAPACITY_LEVEL AS ENUM ('Normal', 'Warning', 'Critical');
+CREATE TYPE SCOPE_PURPOSE AS ENUM ('Dhcp', 'Static', 'Reserved');
+CREATE TYPE ALLOCATION_STRATEGY AS ENUM ('FirstFit', 'LastFit', 'Random', 'Sparse');
 
 CREATE TABLE IF NOT EXISTS vlans (
     id INTEGER,
     description TEXT,
+    custom_fields JSONB NOT NULL DEFAULT '{}',
     PRIMARY KEY (id)
 );
 
+CREATE TABLE IF NOT EXISTS vrfs (
+    id UUID PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL,
+    rd TEXT UNIQUE,
+    description TEXT
+);
+
 CREATE TABLE IF NOT EXISTS networks (
     id UUID PRIMARY KEY,
-    subnet TEXT NOT NULL,
-    used INTEGER NOT NULL,
-    free INTEGER NOT NULL,
+    subnet CIDR NOT NULL,
+    used NUMERIC(39) NOT NULL,
+    free NUMERIC(39) NOT NULL,
     vlan INTEGER,
     description VARCHAR,
     father UUID,
@@ -21,25 +36,50 @@ CREATE TABLE IF NOT EXISTS networks (
     status STATUS_NETWORK,
     kind KIND_NETWORK,
     node UUID,
+    vrf UUID,
+    custom_fields JSONB NOT NULL DEFAULT '{}',
+    warning_threshold SMALLINT CHECK (warning_threshold BETWEEN 0 AND 100),
+    critical_threshold SMALLINT CHECK (critical_threshold BETWEEN 0 AND 100),
+    capacity CAPACITY_LEVEL NOT NULL DEFAULT 'Normal',
+    reserve_first SMALLINT CHECK (reserve_first >= 0),
+    reserve_last SMALLINT CHECK (reserve_last >= 0),
+    allocation ALLOCATION_STRATEGY NOT NULL DEFAULT 'FirstFit',
     FOREIGN KEY (father) REFERENCES networks(id) ON DELETE CASCADE,
-    FOREIGN KEY (vlan) REFERENCES vlans(id) ON DELETE SET NULL ON UPDATE CASCADE
+    FOREIGN KEY (vlan) REFERENCES vlans(id) ON DELETE SET NULL ON UPDATE CASCADE,
+    FOREIGN KEY (vrf) REFERENCES vrfs(id) ON DELETE RESTRICT,
+
+    -- The subnets of a network never overlap, the roots of a vrf are the subnets of the nil uuid
+    CONSTRAINT networks_overlap EXCLUDE USING gist (
+        (COALESCE(vrf, '00000000-0000-0000-0000-000000000000')) WITH =,
+        (COALESCE(father, '00000000-0000-0000-0000-000000000000')) WITH =,
+        subnet inet_ops WITH &&
+    )
 );
 
 CREATE TABLE IF NOT EXISTS addresses (
-    ip TEXT,
+    ip INET,
     network_id UUID,
     status STATUSADDR,
     node_id UUID,
+    custom_fields JSONB NOT NULL DEFAULT '{}',
+    label TEXT,
     PRIMARY KEY (ip, network_id),
     FOREIGN KEY (network_id) REFERENCES networks (id) ON DELETE CASCADE ON UPDATE CASCADE
 );
 
+CREATE INDEX IF NOT EXISTS networks_subnet_idx ON networks USING GIST (subnet inet_ops);
+CREATE INDEX IF NOT EXISTS addresses_ip_idx ON addresses USING GIST (ip inet_ops);
+CREATE INDEX IF NOT EXISTS networks_vrf_idx ON networks (vrf);
+CREATE INDEX IF NOT EXISTS networks_father_idx ON networks (father);
+CREATE INDEX IF NOT EXISTS addresses_network_id_idx ON addresses (network_id);
+
 CREATE TABLE IF NOT EXISTS nodes (
     id UUID,
     hostname TEXT,
     description TEXT,
     username TEXT,
     password TEXT,
+    custom_fields JSONB NOT NULL DEFAULT '{}',
     PRIMARY KEY (id)
 );
 
@@ -51,4 +91,98 @@ CREATE TABLE IF NOT EXISTS users (
     is_active BOOLEAN DEFAULT TRUE,
     create_at TIMESTAMPTZ,
     last_login TIMESTAMPTZ
-);
\ No newline at end of file
+);
+
+CREATE TABLE IF NOT EXISTS custom_fields (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    kind CUSTOM_FIELD_KIND NOT NULL,
+    target CUSTOM_FIELD_TARGET NOT NULL,
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    choices TEXT[],
+    description TEXT,
+    UNIQUE (name, target)
+);
+
+CREATE TABLE IF NOT EXISTS tags (
+    id UUID PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL,
+    color TEXT
+);
+
+CREATE TABLE IF NOT EXISTS network_tags (
+    network_id UUID,
+    tag_id UUID,
+    PRIMARY KEY (network_id, tag_id),
+    FOREIGN KEY (network_id) REFERENCES networks (id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS address_tags (
+    ip INET,
+    network_id UUID,
+    tag_id UUID,
+    PRIMARY KEY (ip, network_id, tag_id),
+    FOREIGN KEY (ip, network_id) REFERENCES addresses (ip, network_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS node_tags (
+    node_id UUID,
+    tag_id UUID,
+    PRIMARY KEY (node_id, tag_id),
+    FOREIGN KEY (node_id) REFERENCES nodes (id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS vlan_tags (
+    vlan_id INTEGER,
+    tag_id UUID,
+    PRIMARY KEY (vlan_id, tag_id),
+    FOREIGN KEY (vlan_id) REFERENCES vlans (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS network_tags_tag_id_idx ON network_tags (tag_id);
+CREATE INDEX IF NOT EXISTS address_tags_tag_id_idx ON address_tags (tag_id);
+CREATE INDEX IF NOT EXISTS node_tags_tag_id_idx ON node_tags (tag_id);
+CREATE INDEX IF NOT EXISTS vlan_tags_tag_id_idx ON vlan_tags (tag_id);
+
+CREATE TABLE IF NOT EXISTS capacity_alerts (
+    id UUID PRIMARY KEY,
+    network_id UUID NOT NULL,
+    previous CAPACITY_LEVEL NOT NULL,
+    level CAPACITY_LEVEL NOT NULL,
+    utilisation DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    FOREIGN KEY (network_id) REFERENCES networks (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS capacity_alerts_network_id_idx ON capacity_alerts (network_id, created_at);
+
+CREATE TABLE IF NOT EXISTS scopes (
+    id UUID PRIMARY KEY,
+    network_id UUID NOT NULL,
+    start_ip INET NOT NULL,
+    end_ip INET NOT NULL,
+    purpose SCOPE_PURPOSE NOT NULL,
+    description TEXT,
+    CHECK (start_ip <= end_ip),
+    FOREIGN KEY (network_id) REFERENCES networks (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS scopes_network_id_idx ON scopes (network_id);
+
+CREATE TABLE IF NOT EXISTS network_status_history (
+    id UUID PRIMARY KEY,
+    network_id UUID NOT NULL,
+    previous STATUS_NETWORK NOT NULL,
+    status STATUS_NETWORK NOT NULL,
+    reason TEXT,
+    user_id UUID,
+    created_at TIMESTAMPTZ NOT NULL,
+    FOREIGN KEY (network_id) REFERENCES networks (id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS network_status_history_network_id_idx ON network_status_history (network_id, created_at);
@@ -2,7 +2,7 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{Data, Ident};
 
-#[proc_macro_derive(Table, attributes(table_name))]
+#[proc_macro_derive(Table, attributes(table_name, order_by))]
 pub fn table_derive(token: TokenStream) -> TokenStream {
     let ast = syn::parse(token).unwrap();
     impl_table_trait(&ast)
@@ -18,6 +18,16 @@ fn impl_table_trait(ast: &syn::DeriveInput) -> TokenStream {
         .and_then(|attr| attr.parse_args::<syn::LitStr>().map(|x| x.value()).ok())
         .unwrap_or(t.to_string().to_lowercase());
 
+    let order_by = match ast
+        .attrs
+        .iter()
+        .find(|x| x.path().is_ident("order_by"))
+        .and_then(|attr| attr.parse_args::<syn::LitStr>().map(|x| x.value()).ok())
+    {
+        Some(e) => quote! { ::std::option::Option::Some(#e) },
+        None => quote! { ::std::option::Option::None },
+    };
+
     let fields = match &ast.data {
         Data::Struct(e) => &e.fields,
         _ => panic!("Derive only pemit in structs"),
@@ -43,6 +53,10 @@ fn impl_table_trait(ast: &syn::DeriveInput) -> TokenStream {
                     #(stringify!(#fields)),*
                 ]
             }
+
+            fn order_by() -> Option<&'static str> {
+                #order_by
+            }
         }
     }
     .into()
@@ -181,7 +195,7 @@ fn impl_map_params(input: &syn::DeriveInput) -> TokenStream {
         _ => panic!("Only struct"),
     }
     .iter()
-    .filter(|x| !x.attrs.iter().any(|x| !x.path().is_ident("IgnoreField")))
+    .filter(|x| !x.attrs.iter().any(|x| x.path().is_ident("IgnoreField")))
     .map(|field| {
         let ty = &field.ty;
         let name = field.ident.as_ref().unwrap();
@@ -0,0 +1,3 @@
+-- The host counters hold up to 2^128 hosts, an ipv6 network doesn't fit in an INTEGER
+ALTER TABLE networks ALTER COLUMN used TYPE NUMERIC(39);
+ALTER TABLE networks ALTER COLUMN free TYPE NUMERIC(39);
@@ -0,0 +1,10 @@
+-- The subnets and addresses are stored as cidr and inet, the text values keep the same format
+ALTER TABLE networks ALTER COLUMN subnet TYPE CIDR USING subnet::cidr;
+ALTER TABLE addresses ALTER COLUMN ip TYPE INET USING ip::inet;
+
+CREATE INDEX IF NOT EXISTS networks_subnet_idx ON networks USING GIST (subnet inet_ops);
+CREATE INDEX IF NOT EXISTS addresses_ip_idx ON addresses USING GIST (ip inet_ops);
+
+-- The deletes cascade through these columns
+CREATE INDEX IF NOT EXISTS networks_father_idx ON networks (father);
+CREATE INDEX IF NOT EXISTS addresses_network_id_idx ON addresses (network_id);
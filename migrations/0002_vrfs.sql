@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS vrfs (
+    id UUID PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL,
+    rd TEXT UNIQUE,
+    description TEXT
+);
+
+-- The current networks belong to the global vrf
+ALTER TABLE networks ADD COLUMN IF NOT EXISTS vrf UUID REFERENCES vrfs(id) ON DELETE RESTRICT;
+
+CREATE INDEX IF NOT EXISTS networks_vrf_idx ON networks (vrf);
@@ -0,0 +1,19 @@
+CREATE TYPE CUSTOM_FIELD_KIND AS ENUM ('String', 'Int', 'Bool', 'Enum', 'Date');
+CREATE TYPE CUSTOM_FIELD_TARGET AS ENUM ('Network', 'Addresses', 'Node', 'Vlan');
+
+CREATE TABLE IF NOT EXISTS custom_fields (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    kind CUSTOM_FIELD_KIND NOT NULL,
+    target CUSTOM_FIELD_TARGET NOT NULL,
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    choices TEXT[],
+    description TEXT,
+    UNIQUE (name, target)
+);
+
+-- The values are validated with the definitions by the server
+ALTER TABLE networks ADD COLUMN IF NOT EXISTS custom_fields JSONB NOT NULL DEFAULT '{}';
+ALTER TABLE addresses ADD COLUMN IF NOT EXISTS custom_fields JSONB NOT NULL DEFAULT '{}';
+ALTER TABLE nodes ADD COLUMN IF NOT EXISTS custom_fields JSONB NOT NULL DEFAULT '{}';
+ALTER TABLE vlans ADD COLUMN IF NOT EXISTS custom_fields JSONB NOT NULL DEFAULT '{}';
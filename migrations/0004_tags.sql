@@ -0,0 +1,43 @@
+CREATE TABLE IF NOT EXISTS tags (
+    id UUID PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL,
+    color TEXT
+);
+
+CREATE TABLE IF NOT EXISTS network_tags (
+    network_id UUID,
+    tag_id UUID,
+    PRIMARY KEY (network_id, tag_id),
+    FOREIGN KEY (network_id) REFERENCES networks (id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS address_tags (
+    ip INET,
+    network_id UUID,
+    tag_id UUID,
+    PRIMARY KEY (ip, network_id, tag_id),
+    FOREIGN KEY (ip, network_id) REFERENCES addresses (ip, network_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS node_tags (
+    node_id UUID,
+    tag_id UUID,
+    PRIMARY KEY (node_id, tag_id),
+    FOREIGN KEY (node_id) REFERENCES nodes (id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS vlan_tags (
+    vlan_id INTEGER,
+    tag_id UUID,
+    PRIMARY KEY (vlan_id, tag_id),
+    FOREIGN KEY (vlan_id) REFERENCES vlans (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS network_tags_tag_id_idx ON network_tags (tag_id);
+CREATE INDEX IF NOT EXISTS address_tags_tag_id_idx ON address_tags (tag_id);
+CREATE INDEX IF NOT EXISTS node_tags_tag_id_idx ON node_tags (tag_id);
+CREATE INDEX IF NOT EXISTS vlan_tags_tag_id_idx ON vlan_tags (tag_id);
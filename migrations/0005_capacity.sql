@@ -0,0 +1,18 @@
+CREATE TYPE CAPACITY_LEVEL AS ENUM ('Normal', 'Warning', 'Critical');
+
+-- The networks without thresholds use the global thresholds of the server
+ALTER TABLE networks ADD COLUMN IF NOT EXISTS warning_threshold SMALLINT CHECK (warning_threshold BETWEEN 0 AND 100);
+ALTER TABLE networks ADD COLUMN IF NOT EXISTS critical_threshold SMALLINT CHECK (critical_threshold BETWEEN 0 AND 100);
+ALTER TABLE networks ADD COLUMN IF NOT EXISTS capacity CAPACITY_LEVEL NOT NULL DEFAULT 'Normal';
+
+CREATE TABLE IF NOT EXISTS capacity_alerts (
+    id UUID PRIMARY KEY,
+    network_id UUID NOT NULL,
+    previous CAPACITY_LEVEL NOT NULL,
+    level CAPACITY_LEVEL NOT NULL,
+    utilisation DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    FOREIGN KEY (network_id) REFERENCES networks (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS capacity_alerts_network_id_idx ON capacity_alerts (network_id, created_at);
@@ -0,0 +1,15 @@
+CREATE TYPE SCOPE_PURPOSE AS ENUM ('Dhcp', 'Static', 'Reserved');
+
+-- The overlap of the scopes of a network is checked by the server
+CREATE TABLE IF NOT EXISTS scopes (
+    id UUID PRIMARY KEY,
+    network_id UUID NOT NULL,
+    start_ip INET NOT NULL,
+    end_ip INET NOT NULL,
+    purpose SCOPE_PURPOSE NOT NULL,
+    description TEXT,
+    CHECK (start_ip <= end_ip),
+    FOREIGN KEY (network_id) REFERENCES networks (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS scopes_network_id_idx ON scopes (network_id);
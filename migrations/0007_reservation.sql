@@ -0,0 +1,5 @@
+-- The networks without a policy use the global policy of the server
+ALTER TABLE networks ADD COLUMN IF NOT EXISTS reserve_first SMALLINT CHECK (reserve_first >= 0);
+ALTER TABLE networks ADD COLUMN IF NOT EXISTS reserve_last SMALLINT CHECK (reserve_last >= 0);
+
+ALTER TABLE addresses ADD COLUMN IF NOT EXISTS label TEXT;
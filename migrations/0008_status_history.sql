@@ -0,0 +1,16 @@
+-- The networks could be requested as Assigned, but the type didn't have the value
+ALTER TYPE STATUS_NETWORK ADD VALUE IF NOT EXISTS 'Assigned';
+
+CREATE TABLE IF NOT EXISTS network_status_history (
+    id UUID PRIMARY KEY,
+    network_id UUID NOT NULL,
+    previous STATUS_NETWORK NOT NULL,
+    status STATUS_NETWORK NOT NULL,
+    reason TEXT,
+    user_id UUID,
+    created_at TIMESTAMPTZ NOT NULL,
+    FOREIGN KEY (network_id) REFERENCES networks (id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS network_status_history_network_id_idx ON network_status_history (network_id, created_at);
@@ -0,0 +1,4 @@
+CREATE TYPE ALLOCATION_STRATEGY AS ENUM ('FirstFit', 'LastFit', 'Random', 'Sparse');
+
+-- The addresses were always allocated from the bottom of the network
+ALTER TABLE networks ADD COLUMN IF NOT EXISTS allocation ALLOCATION_STRATEGY NOT NULL DEFAULT 'FirstFit';
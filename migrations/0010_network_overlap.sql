@@ -0,0 +1,29 @@
+-- The overlap is checked by the server too, the constraint refuses the networks created at the same time
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+-- The overlapping siblings stored before the constraint are listed, they have to be nested or
+-- deleted by hand because their addresses and counters depend on the network that is kept
+DO $$
+DECLARE
+    conflicts TEXT;
+BEGIN
+    SELECT string_agg(format('%s (%s) overlaps %s (%s)', a.subnet, a.id, b.subnet, b.id), E'\n')
+    INTO conflicts
+    FROM networks a
+    JOIN networks b ON a.id < b.id
+        AND a.vrf IS NOT DISTINCT FROM b.vrf
+        AND a.father IS NOT DISTINCT FROM b.father
+        AND a.subnet && b.subnet;
+
+    IF conflicts IS NOT NULL THEN
+        RAISE EXCEPTION 'Overlapping sibling networks, nest or delete them before the migration'
+            USING DETAIL = conflicts;
+    END IF;
+END $$;
+
+-- The subnets of a network never overlap, the roots of a vrf are the subnets of the nil uuid
+ALTER TABLE networks ADD CONSTRAINT networks_overlap EXCLUDE USING gist (
+    (COALESCE(vrf, '00000000-0000-0000-0000-000000000000')) WITH =,
+    (COALESCE(father, '00000000-0000-0000-0000-000000000000')) WITH =,
+    subnet inet_ops WITH &&
+);
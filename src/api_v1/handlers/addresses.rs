@@ -1,19 +1,27 @@
 use super::{
-    BATCH_SIZE, Json, Path, Query, ResponseDefault, State, StateType,
+    BATCH_SIZE, Json, Path, Query, ResponseDefault, State, StateType, custom_field,
     entries::{
-        models::AddrCrateEntry,
-        params::{IpNetParamNonOption, PaginationParams, ParamAddrFilter},
+        models::{AddrAllocateEntry, AddrCrateEntry},
+        params::{IpNetParamNonOption, PaginationParams, ParamAddrFilter, ParamTags},
     },
     extractors::IsAdministrator,
 };
 use crate::{
     database::{
-        repository::Repository,
+        repository::{IpOperation, Repository, error::RepositoryError},
         transaction::{BuilderPgTransaction, Transaction as _},
     },
-    models::network::{
-        Kind, NetwCondition, Network, UpdateHostCount,
-        addresses::{AddrCondition, Addresses, StatusAddr},
+    models::{
+        custom_field::FieldTarget,
+        network::{
+            Kind, NetwCondition, Network, UpdateHostCount,
+            addresses::{
+                AddrCondition, AddrIpCondition, AddrMode, AddrPage, AddrRange, Addresses,
+                StatusAddr, UpdateAddr,
+            },
+            scope::{Scope, ScopeCondition},
+        },
+        tag::TagJoin,
     },
     response::ResponseQuery,
 };
@@ -21,6 +29,7 @@ use axum::http::StatusCode;
 use ipnet::IpNet;
 use libipam::response_error::ResponseError;
 use serde_json::json;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 pub async fn insert(
@@ -28,7 +37,19 @@ pub async fn insert(
     _: IsAdministrator,
     Json(new_addr): Json<AddrCrateEntry>,
 ) -> ResponseDefault<()> {
-    Ok(state.insert::<Addresses>(new_addr.into()).await?.into())
+    let new_addr = Addresses::from(new_addr);
+
+    if !new_addr.is_stored(state.addresses_mode()) {
+        return Err(ResponseError::builder()
+            .title("Address not stored".to_string())
+            .detail("In sparse mode an address needs a status or a node to be stored".to_string())
+            .status(StatusCode::BAD_REQUEST)
+            .build());
+    }
+
+    custom_field::validate(&state, FieldTarget::Addresses, &new_addr.custom_fields).await?;
+
+    Ok(state.insert::<Addresses>(new_addr).await?.into())
 }
 
 pub async fn create_all_ip_addresses(
@@ -41,52 +62,300 @@ pub async fn create_all_ip_addresses(
         .await?
         .remove(0);
 
-    if network.kind != Kind::Network {
+    // The addresses of a pool are allocated from its scopes
+    if network.kind == Kind::Pool {
         return Err(ResponseError::builder()
             .title("Cannot create those ips".to_string())
-            .detail("This network is not set up to independent IPs".to_string())
+            .detail(format!(
+                "The network {} is a pool, its addresses are allocated from its scopes",
+                network.subnet
+            ))
             .status(StatusCode::FORBIDDEN)
             .build());
     }
 
-    let len;
+    if state.addresses_mode() == AddrMode::Sparse {
+        return Err(ResponseError::builder()
+            .title("Cannot create those ips".to_string())
+            .detail("In sparse mode the unknown addresses aren't stored".to_string())
+            .status(StatusCode::BAD_REQUEST)
+            .build());
+    }
+
+    let addrs = network.addresses();
+    let len = addrs.len();
+
+    if len > u128::from(u32::MAX) {
+        return Err(ResponseError::builder()
+            .title("Cannot create those ips".to_string())
+            .detail(format!(
+                "The network {} has too many addresses to store each of them",
+                network.subnet
+            ))
+            .status(StatusCode::BAD_REQUEST)
+            .build());
+    }
+
+    let reserved = network
+        .reserved(state.reserve_policy())
+        .into_iter()
+        .map(|x| (x.ip.addr(), x))
+        .collect::<HashMap<_, _>>();
 
     let _permit = state.heavy_task().acquire().await;
 
-    match network.addresses() {
-        Ok(e) if e.len() > BATCH_SIZE => {
-            let addrs = e.batch(BATCH_SIZE);
-            len = addrs.inner_len();
-
-            let mut transaction = state.transaction().await?;
-            for addr in addrs {
-                if let Err(e) = transaction.insert_many(addr).await {
-                    transaction.rollback().await?;
-                    return Err(ResponseError::from(e));
-                }
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        // The stored addresses, as the ones reserved when the network was created, are kept
+        let stored = transaction
+            .get::<Addresses>(
+                AddrCondition {
+                    network_id: Some(network.id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+            .into_iter()
+            .map(|x| x.ip.addr())
+            .collect::<HashSet<_>>();
+
+        let mut created = 0;
+        let mut new_reserved = 0;
+
+        for addrs in addrs.batch(BATCH_SIZE) {
+            let addrs = addrs
+                .into_iter()
+                .filter(|x| !stored.contains(&x.ip.addr()))
+                .map(|x| reserved.get(&x.ip.addr()).cloned().unwrap_or(x))
+                .collect::<Vec<_>>();
+
+            if addrs.is_empty() {
+                continue;
             }
 
-            transaction.commit().await?;
+            created += addrs.len();
+            new_reserved += addrs
+                .iter()
+                .filter(|x| x.status == StatusAddr::Reserved)
+                .count();
+
+            transaction.insert_many(addrs).await?;
         }
-        Ok(e) => {
-            len = e.len();
-            _ = state.insert_many(e.collect::<Vec<_>>()).await?;
+
+        if new_reserved != 0 {
+            update_host_count(&mut transaction, network, |x| {
+                x.less_free_more_used(new_reserved as u128);
+            })
+            .await?;
         }
+
+        Ok::<_, ResponseError>(created)
+    }
+    .await;
+
+    let created = match resp {
+        Ok(e) => e,
         Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    transaction.commit().await?;
+
+    let metadata = Some(json!({
+        "row_affect": created,
+        "status": StatusCode::OK.as_u16(),
+        "success": true
+    }));
+
+    Ok(ResponseQuery::new(None, metadata, None, StatusCode::OK))
+}
+
+/// Picks free addresses of the network with its allocation strategy, or the strategy of the
+/// request, and marks them as used in one transaction.
+///
+/// The network row is locked until the end of the transaction, so concurrent requests
+/// over the same network wait and never get the same address
+pub async fn allocate(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(network_id): Path<Uuid>,
+    Json(AddrAllocateEntry {
+        count,
+        node_id,
+        status,
+        scope,
+        allocation,
+    }): Json<AddrAllocateEntry>,
+) -> ResponseDefault<Vec<Addresses>> {
+    let count = count.unwrap_or(1);
+    let status = status.unwrap_or(StatusAddr::Reserved);
+
+    if !(1..=BATCH_SIZE).contains(&count) || status == StatusAddr::Unknown {
+        return Err(ResponseError::builder()
+            .title("Invalid allocation".to_string())
+            .detail(format!(
+                "The count must be between 1 and {BATCH_SIZE} and the status cannot be Unknown"
+            ))
+            .status(StatusCode::BAD_REQUEST)
+            .build());
+    }
+
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        let network = transaction
+            .get_for_update::<Network>(NetwCondition::p_key(network_id))
+            .await?;
+
+        let range = network.addresses();
+
+        // Positions of the range where the addresses are allocated
+        let positions = match scope {
+            Some(id) => {
+                let scope = transaction
+                    .get_for_update::<Scope>(ScopeCondition {
+                        id: Some(id),
+                        network_id: Some(network_id),
+                        ..Default::default()
+                    })
+                    .await?;
+
+                range
+                    .index_of(scope.start_ip)
+                    .zip(range.index_of(scope.end_ip))
+                    .map(|(start, end)| start..end + 1)
+                    .ok_or(
+                        ResponseError::builder()
+                            .title("Invalid scope".to_string())
+                            .detail(format!(
+                                "The scope from {} to {} isn't inside {}",
+                                scope.start_ip, scope.end_ip, network.subnet
+                            ))
+                            .status(StatusCode::CONFLICT)
+                            .build(),
+                    )?
+            }
+            None if network.kind != Kind::Network => {
+                return Err(ResponseError::builder()
+                    .title("Cannot allocate ips".to_string())
+                    .detail(
+                        "This network is not set up to independent IPs, a pool needs a scope"
+                            .to_string(),
+                    )
+                    .status(StatusCode::FORBIDDEN)
+                    .build());
+            }
+            None => 0..range.len(),
+        };
+
+        let stored = transaction
+            .get::<Addresses>(
+                AddrCondition {
+                    network_id: Some(network_id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+            .into_iter()
+            .filter_map(|x| range.index_of(x.ip.addr()).map(|index| (index, x)))
+            .collect::<HashMap<_, _>>();
+
+        let taken = stored
+            .iter()
+            .filter(|(_, x)| x.is_stored(AddrMode::Sparse))
+            .map(|(index, _)| *index)
+            .collect::<Vec<_>>();
+
+        let strategy = allocation.unwrap_or(network.allocation);
+        let free = range.free(strategy, taken, count, positions);
+
+        if free.len() < count {
             return Err(ResponseError::builder()
-                .detail(e.to_string())
-                .status(StatusCode::BAD_REQUEST)
+                .title("Network exhausted".to_string())
+                .detail(format!(
+                    "The {} {} only has {} free addresses",
+                    if scope.is_some() {
+                        "scope of"
+                    } else {
+                        "network"
+                    },
+                    network.subnet,
+                    free.len()
+                ))
+                .status(StatusCode::CONFLICT)
                 .build());
         }
+
+        let mut allocated = Vec::with_capacity(count);
+        let mut to_insert = Vec::new();
+
+        for index in free {
+            if let Some(mut addr) = stored.get(&index).cloned() {
+                // in dense mode the free address may already be stored as unknown
+                transaction
+                    .update::<Addresses, _, _>(
+                        UpdateAddr {
+                            status: Some(status),
+                            node_id,
+                            ..Default::default()
+                        },
+                        AddrCondition::p_key(addr.ip, network_id),
+                    )
+                    .await?;
+
+                addr.status = status;
+                addr.node_id = node_id;
+                allocated.push(addr);
+            } else if let Some(mut addr) = range.get(index) {
+                addr.status = status;
+                addr.node_id = node_id;
+                to_insert.push(addr.clone());
+                allocated.push(addr);
+            }
+        }
+
+        if !to_insert.is_empty() {
+            transaction.insert_many(to_insert).await?;
+        }
+
+        update_host_count(&mut transaction, network, |x| {
+            x.less_free_more_used(count as u128);
+        })
+        .await?;
+
+        Ok(allocated)
     }
+    .await;
+
+    let allocated = match resp {
+        Ok(e) => e,
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    transaction.commit().await?;
 
     let metadata = Some(json!({
-        "row_affect": len,
+        "length": allocated.len(),
+        "success": true,
         "status": StatusCode::OK.as_u16(),
-        "success": true
     }));
 
-    Ok(ResponseQuery::new(None, metadata, None, StatusCode::OK))
+    Ok(ResponseQuery::new(
+        Some(allocated),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
 }
 
 pub async fn update(
@@ -94,8 +363,12 @@ pub async fn update(
     _: IsAdministrator,
     Path(network_id): Path<Uuid>,
     Query(IpNetParamNonOption { ip }): Query<IpNetParamNonOption>,
-    Json(updater): Json<AddrCondition>,
+    Json(updater): Json<UpdateAddr>,
 ) -> Result<StatusCode, ResponseError> {
+    if let Some(custom_fields) = &updater.custom_fields {
+        custom_field::validate(&state, FieldTarget::Addresses, custom_fields).await?;
+    }
+
     if updater.ip.is_some_and(|x| x != ip) || updater.network_id.is_some_and(|x| x != network_id) {
         let network_target = state
             .get_one::<Network>(NetwCondition::p_key(
@@ -179,6 +452,9 @@ pub async fn update(
 
         transaction.commit().await?;
         Ok(StatusCode::OK)
+    } else if state.addresses_mode() == AddrMode::Sparse {
+        update_sparse(&state, network_id, ip, updater).await?;
+        Ok(StatusCode::OK)
     } else {
         state
             .update::<Addresses, _>(updater, AddrCondition::p_key(ip, network_id))
@@ -187,6 +463,68 @@ pub async fn update(
     }
 }
 
+/// In sparse mode the address may not be stored yet, or may stop being stored after the update
+async fn update_sparse(
+    state: &StateType,
+    network_id: Uuid,
+    ip: IpNet,
+    updater: UpdateAddr,
+) -> Result<(), ResponseError> {
+    let (mut addr, stored) = match state
+        .get_one::<Addresses>(AddrCondition::p_key(ip, network_id))
+        .await
+    {
+        Ok(e) => (e, true),
+        Err(RepositoryError::RowNotFound) => {
+            let range = state
+                .get_one::<Network>(NetwCondition::p_key(network_id))
+                .await?
+                .addresses();
+
+            let addr = range.index_of(ip.addr()).and_then(|x| range.get(x)).ok_or(
+                ResponseError::builder()
+                    .detail(format!("The ip {ip} isn't a host of the network"))
+                    .status(StatusCode::BAD_REQUEST)
+                    .build(),
+            )?;
+
+            (addr, false)
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if let Some(status) = updater.status {
+        addr.status = status;
+    }
+
+    if updater.node_id.is_some() {
+        addr.node_id = updater.node_id;
+    }
+
+    if let Some(custom_fields) = updater.custom_fields.clone() {
+        addr.custom_fields = custom_fields;
+    }
+
+    match (stored, addr.is_stored(AddrMode::Sparse)) {
+        (true, true) => {
+            state
+                .update::<Addresses, _>(updater, AddrCondition::p_key(ip, network_id))
+                .await?;
+        }
+        (true, false) => {
+            state
+                .delete::<Addresses>(AddrCondition::p_key(ip, network_id))
+                .await?;
+        }
+        (false, true) => {
+            state.insert(addr).await?;
+        }
+        (false, false) => {}
+    }
+
+    Ok(())
+}
+
 pub async fn get(
     State(state): State<StateType>,
     Query(PaginationParams { limit, offset }): Query<PaginationParams>,
@@ -195,27 +533,143 @@ pub async fn get(
         ip,
         node_id,
         status,
-        sort,
+        custom_fields,
     }): Query<ParamAddrFilter>,
+    Query(tags): Query<ParamTags>,
 ) -> ResponseDefault<Vec<Addresses>> {
-    let mut addrs = state
-        .get::<Addresses>(
-            AddrCondition {
-                network_id: Some(network_id),
-                ip,
-                node_id,
-                status,
-            },
-            limit,
-            offset,
-        )
-        .await?;
+    let tags = tags.filter(TagJoin::ADDRESSES);
+
+    // Only the stored addresses can have a node, custom fields, tags or a status other than unknown,
+    // and in dense mode every address is stored
+    if state.addresses_mode() == AddrMode::Dense
+        || node_id.is_some()
+        || custom_fields.is_some()
+        || tags.is_some()
+        || status.is_some_and(|x| x != StatusAddr::Unknown)
+    {
+        let addrs = state
+            .get::<Addresses>(
+                AddrCondition {
+                    network_id: Some(network_id),
+                    ip,
+                    node_id,
+                    status,
+                    custom_fields,
+                    tags,
+                },
+                limit,
+                offset,
+            )
+            .await?;
 
-    if let Some(true) = sort {
-        addrs.sort_by_key(|x| x.ip);
+        return Ok(ResponseQuery::new(Some(addrs), None, None, StatusCode::OK));
     }
 
-    Ok(ResponseQuery::new(Some(addrs), None, None, StatusCode::OK))
+    let range = state
+        .get_one::<Network>(NetwCondition::p_key(network_id))
+        .await?
+        .addresses();
+
+    // When the filter is the unknown status, the stored addresses with other status are skipped
+    let skip_used = status.is_some();
+
+    let (data, total) = if let Some(ip) = ip {
+        let data = match state
+            .get_one::<Addresses>(AddrCondition::p_key(ip, network_id))
+            .await
+        {
+            Ok(e) if skip_used && e.status != StatusAddr::Unknown => Vec::new(),
+            Ok(e) => vec![e],
+            Err(RepositoryError::RowNotFound) => range
+                .index_of(ip.addr())
+                .and_then(|x| range.get(x))
+                .into_iter()
+                .collect(),
+            Err(e) => return Err(e.into()),
+        };
+        let len = data.len() as u128;
+
+        (data, len)
+    } else {
+        let mut total = range.len();
+
+        if skip_used {
+            for status in StatusAddr::USED {
+                total = total.saturating_sub(u128::from(
+                    state
+                        .count::<Addresses>(AddrCondition {
+                            network_id: Some(network_id),
+                            status: Some(status),
+                            ..Default::default()
+                        })
+                        .await?,
+                ));
+            }
+        }
+
+        let offset = offset.and_then(|x| u128::try_from(x).ok()).unwrap_or(0);
+        let limit = limit
+            .and_then(|x| usize::try_from(x).ok())
+            .map_or(BATCH_SIZE, |x| x.min(BATCH_SIZE));
+
+        (page(&state, &range, offset, limit, skip_used).await?, total)
+    };
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "total": total,
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// Reads the stored addresses of the page in batches, from the start of the page
+async fn page(
+    state: &StateType,
+    range: &AddrRange,
+    offset: u128,
+    limit: usize,
+    skip_used: bool,
+) -> Result<Vec<Addresses>, ResponseError> {
+    let mut page = AddrPage::new(range, offset, limit, skip_used);
+
+    while let Some(ip) = page.next_ip() {
+        let stored = match state
+            .get::<Addresses>(
+                AddrIpCondition {
+                    ip: Some(IpOperation::From(ip)),
+                    network_id: Some(range.network_id),
+                    ..Default::default()
+                },
+                Some(BATCH_SIZE as i32),
+                None,
+            )
+            .await
+        {
+            Ok(e) => e,
+            Err(RepositoryError::RowNotFound) => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let last = stored.len() < BATCH_SIZE;
+
+        for addr in stored {
+            page.push(addr);
+        }
+
+        if last {
+            break;
+        }
+    }
+
+    Ok(page.finish())
 }
 
 pub async fn delete(
@@ -231,14 +685,44 @@ pub async fn delete(
     Ok(del.into())
 }
 
+/// Addresses reserved by the policy of the network, they're counted as used but don't put the
+/// network in use
+pub async fn policy_reserved(
+    transaction: &mut BuilderPgTransaction<'_>,
+    network_id: Uuid,
+) -> Result<Vec<Addresses>, ResponseError> {
+    Ok(transaction
+        .get::<Addresses>(
+            AddrCondition {
+                network_id: Some(network_id),
+                status: Some(StatusAddr::Reserved),
+                ..Default::default()
+            },
+            None,
+            None,
+        )
+        .await?
+        .into_iter()
+        .filter(Addresses::is_policy_reserved)
+        .collect())
+}
+
+/// Applies `action` to the counters of the network and of its ancestors.
+///
+/// Every row is read again with `FOR UPDATE`, so concurrent changes in sibling networks don't
+/// overwrite the counters of their common ancestors
 pub async fn update_host_count<F>(
     transaction: &mut BuilderPgTransaction<'_>,
-    mut network: Network,
+    network: Network,
     mut action: F,
 ) -> Result<(), ResponseError>
 where
     F: FnMut(&mut UpdateHostCount),
 {
+    let mut network = transaction
+        .get_for_update::<Network>(NetwCondition::p_key(network.id))
+        .await?;
+
     let mut hc = network.update_host_count();
     action(&mut hc);
 
@@ -248,9 +732,8 @@ where
 
     while let Some(father) = network.father {
         network = transaction
-            .get(NetwCondition::p_key(father), None, None)
-            .await?
-            .remove(0);
+            .get_for_update::<Network>(NetwCondition::p_key(father))
+            .await?;
 
         let mut hc = network.update_host_count();
         action(&mut hc);
@@ -1,6 +1,6 @@
 use super::{
     IsAdministrator, Json, Path, Repository, ResponseError, Role, State, StateType, StatusCode,
-    Uri, Uuid, entries, entries::models::UserEntry, models,
+    Uri, Uuid, entries, entries::models::UserEntry, extractors::CurrentUser, models,
 };
 use crate::{
     models::{
@@ -167,5 +167,6 @@ pub async fn verify_token(
     })??;
 
     req.extensions_mut().insert(claim.role);
+    req.extensions_mut().insert(CurrentUser(claim.id));
     Ok(next.run(req).await)
 }
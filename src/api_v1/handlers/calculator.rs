@@ -0,0 +1,56 @@
+use super::{
+    Json, Query, ResponseDefault, ResponseError, StatusCode,
+    entries::params::{IpNetParamNonOption, ParamRange},
+};
+use crate::response::ResponseQuery;
+use ipnet::IpNet;
+use libipam::services::calculator::{self, NetworkInfo};
+use serde_json::json;
+
+pub async fn info(
+    Query(IpNetParamNonOption { ip }): Query<IpNetParamNonOption>,
+) -> ResponseDefault<NetworkInfo> {
+    Ok(ResponseQuery::new(
+        Some(calculator::info(ip)),
+        None,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+pub async fn range(
+    Query(ParamRange { start, end }): Query<ParamRange>,
+) -> ResponseDefault<Vec<IpNet>> {
+    let data = calculator::range_to_networks(start, end).map_err(|e| {
+        ResponseError::builder()
+            .detail(e.to_string())
+            .status(StatusCode::BAD_REQUEST)
+            .build()
+    })?;
+
+    let metadata = Some(json!({
+        "length": data.len(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+pub async fn summarize(Json(networks): Json<Vec<IpNet>>) -> ResponseDefault<Vec<IpNet>> {
+    let data = calculator::summarize(&networks);
+
+    let metadata = Some(json!({
+        "length": data.len(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
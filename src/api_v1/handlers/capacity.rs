@@ -0,0 +1,108 @@
+use super::{
+    PaginationParams, Query, Repository, ResponseDefault, ResponseError, State, StateType,
+    StatusCode, entries,
+};
+use crate::{
+    database::repository::error::RepositoryError,
+    models::{
+        capacity::{CapacityAlert, CapacityAlertCondition, OverThreshold},
+        network::{NetwCondition, Network},
+    },
+    response::ResponseQuery,
+};
+use entries::params::ParamCapacity;
+use libipam::services::capacity::{Level, utilisation};
+use serde_json::json;
+
+/// Networks with the utilisation over their thresholds, from the most used.
+///
+/// The utilisation is calculated with the current counters, not with the last evaluation
+pub async fn over_threshold(
+    State(state): State<StateType>,
+    Query(ParamCapacity { level }): Query<ParamCapacity>,
+) -> ResponseDefault<Vec<OverThreshold>> {
+    let networks = match state
+        .get::<Network>(NetwCondition::default(), None, None)
+        .await
+    {
+        Ok(e) => e,
+        Err(RepositoryError::RowNotFound) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let min = level.unwrap_or(Level::Warning).max(Level::Warning);
+
+    let mut data = networks
+        .into_iter()
+        .filter_map(|network| {
+            let thresholds = network.thresholds(state.thresholds());
+            let utilisation = utilisation(network.used, network.free);
+            let level = thresholds.level(utilisation);
+
+            (level >= min).then_some(OverThreshold {
+                network,
+                utilisation,
+                level,
+                thresholds,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    data.sort_by(|a, b| b.utilisation.total_cmp(&a.utilisation));
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "warning": state.thresholds().warning(),
+        "critical": state.thresholds().critical(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// Changes of the capacity level recorded by the evaluator, from the newest
+pub async fn alerts(
+    State(state): State<StateType>,
+    Query(params): Query<CapacityAlertCondition>,
+    Query(PaginationParams { offset, limit }): Query<PaginationParams>,
+) -> ResponseDefault<Vec<CapacityAlert>> {
+    let data = state.get::<CapacityAlert>(params, limit, offset).await?;
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// Checks the thresholds of a network with the global thresholds
+pub fn validate(
+    state: &StateType,
+    warning: Option<i16>,
+    critical: Option<i16>,
+) -> Result<(), ResponseError> {
+    state
+        .thresholds()
+        .with(warning, critical)
+        .map(|_| ())
+        .map_err(|e| {
+            ResponseError::builder()
+                .title("Invalid thresholds".to_string())
+                .detail(e.to_string())
+                .status(StatusCode::BAD_REQUEST)
+                .build()
+        })
+}
@@ -0,0 +1,270 @@
+use super::{
+    IsAdministrator, Json, PaginationParams, Path, Query, Repository, ResponseDefault,
+    ResponseError, State, StateType, StatusCode, Uuid, entries,
+};
+use crate::{
+    database::{
+        repository::{MapQuery, Table, TypeTable, error::RepositoryError},
+        transaction::{BuilderPgTransaction, Transaction as _},
+    },
+    models::{
+        custom_field::{
+            CustomField, CustomFieldCondition, CustomFields, CustomFieldsFilter, FieldTarget,
+            UpdateCustomField,
+        },
+        network::{
+            NetwCondition, Network,
+            addresses::{AddrCondition, Addresses},
+        },
+        node::{Node, NodeCondition},
+        vlan::{Vlan, VlanCondition},
+    },
+    response::ResponseQuery,
+};
+use entries::models::CustomFieldCreateEntry;
+use serde_json::json;
+use std::collections::HashMap;
+
+pub async fn create(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Json(field): Json<CustomFieldCreateEntry>,
+) -> ResponseDefault<()> {
+    let field = CustomField::from(field);
+
+    field.check().map_err(|e| {
+        ResponseError::builder()
+            .title("Invalid custom field".to_string())
+            .detail(e.to_string())
+            .status(StatusCode::BAD_REQUEST)
+            .build()
+    })?;
+
+    Ok(state.insert::<CustomField>(field).await?.into())
+}
+
+pub async fn get(
+    State(state): State<StateType>,
+    Query(params): Query<CustomFieldCondition>,
+    Query(PaginationParams { offset, limit }): Query<PaginationParams>,
+) -> ResponseDefault<Vec<CustomField>> {
+    let data = state.get::<CustomField>(params, limit, offset).await?;
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+pub async fn update(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+    Json(updater): Json<UpdateCustomField>,
+) -> ResponseDefault<()> {
+    let old = state
+        .get_one::<CustomField>(CustomFieldCondition::p_key(id))
+        .await?;
+
+    let mut field = old.clone();
+
+    if let Some(choices) = updater.choices.clone() {
+        field.choices = Some(choices);
+    }
+
+    field.check().map_err(|e| {
+        ResponseError::builder()
+            .title("Invalid custom field".to_string())
+            .detail(e.to_string())
+            .status(StatusCode::BAD_REQUEST)
+            .build()
+    })?;
+
+    // The stored values are checked with the new definition
+    let mut filters = Vec::new();
+
+    if updater.required == Some(true) && !old.required {
+        filters.push(None);
+    }
+
+    if let Some(choices) = &field.choices {
+        filters.extend(
+            old.choices
+                .iter()
+                .flatten()
+                .filter(|x| !choices.contains(x))
+                .map(|x| Some(x.clone())),
+        );
+    }
+
+    for value in filters {
+        let filter = CustomFieldsFilter::new(field.name.clone(), value.clone());
+        let count = count_values(&state, field.target, filter).await?;
+
+        if count != 0 {
+            let detail = match value {
+                Some(value) => format!("{count} objects have the value {value} of {}", field.name),
+                None => format!("{count} objects don't have a value of {}", field.name),
+            };
+
+            return Err(ResponseError::builder()
+                .title("Custom field in use".to_string())
+                .detail(detail)
+                .status(StatusCode::CONFLICT)
+                .build());
+        }
+    }
+
+    Ok(state
+        .update::<CustomField, _>(updater, CustomFieldCondition::p_key(id))
+        .await?
+        .into())
+}
+
+/// The values of the field are removed from the objects of its target
+pub async fn delete(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+) -> ResponseDefault<()> {
+    let field = state
+        .get_one::<CustomField>(CustomFieldCondition::p_key(id))
+        .await?;
+
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        let resp = transaction
+            .delete::<CustomField, _>(CustomFieldCondition::p_key(id))
+            .await?;
+
+        match field.target {
+            FieldTarget::Network => {
+                remove_values::<Network, _>(&mut transaction, field.name, NetwCondition::default())
+                    .await?;
+            }
+            FieldTarget::Addresses => {
+                remove_values::<Addresses, _>(
+                    &mut transaction,
+                    field.name,
+                    AddrCondition::default(),
+                )
+                .await?;
+            }
+            FieldTarget::Node => {
+                remove_values::<Node, _>(&mut transaction, field.name, NodeCondition::default())
+                    .await?;
+            }
+            FieldTarget::Vlan => {
+                remove_values::<Vlan, _>(&mut transaction, field.name, VlanCondition::default())
+                    .await?;
+            }
+        }
+
+        Ok::<_, RepositoryError>(resp)
+    }
+    .await;
+
+    let resp = match resp {
+        Ok(e) => e,
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e.into());
+        }
+    };
+
+    transaction.commit().await?;
+
+    Ok(resp.into())
+}
+
+/// Number of objects of the target that match the filter
+async fn count_values(
+    state: &StateType,
+    target: FieldTarget,
+    filter: CustomFieldsFilter,
+) -> Result<u64, RepositoryError> {
+    let custom_fields = Some(filter);
+
+    match target {
+        FieldTarget::Network => {
+            state
+                .count::<Network>(NetwCondition {
+                    custom_fields,
+                    ..Default::default()
+                })
+                .await
+        }
+        FieldTarget::Addresses => {
+            state
+                .count::<Addresses>(AddrCondition {
+                    custom_fields,
+                    ..Default::default()
+                })
+                .await
+        }
+        FieldTarget::Node => {
+            state
+                .count::<Node>(NodeCondition {
+                    custom_fields,
+                    ..Default::default()
+                })
+                .await
+        }
+        FieldTarget::Vlan => {
+            state
+                .count::<Vlan>(VlanCondition {
+                    custom_fields,
+                    ..Default::default()
+                })
+                .await
+        }
+    }
+}
+
+async fn remove_values<T: Table, M: MapQuery>(
+    transaction: &mut BuilderPgTransaction<'_>,
+    name: String,
+    condition: M,
+) -> Result<(), RepositoryError> {
+    transaction
+        .update::<T, _, _>(
+            HashMap::from([("custom_fields", TypeTable::RemoveCustomField(name))]),
+            condition,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Checks the custom fields of an object with the definitions of its target
+pub async fn validate(
+    state: &StateType,
+    target: FieldTarget,
+    values: &CustomFields,
+) -> Result<(), ResponseError> {
+    let definitions = match state
+        .get::<CustomField>(CustomFieldCondition::target(target), None, None)
+        .await
+    {
+        Ok(e) => e,
+        Err(RepositoryError::RowNotFound) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    values.validate(&definitions).map_err(|e| {
+        ResponseError::builder()
+            .title("Invalid custom fields".to_string())
+            .detail(e.to_string())
+            .status(StatusCode::BAD_REQUEST)
+            .build()
+    })
+}
@@ -1,11 +1,27 @@
-use super::super::models::{network::Network, node::Node, user::User};
+use super::super::models::{
+    custom_field::{CustomField, CustomFields, FieldKind, FieldTarget},
+    network::Network,
+    node::Node,
+    tag::Tag,
+    user::User,
+    vrf::Vrf,
+};
 use crate::models::network::{
     Kind, StatusNetwork,
     addresses::{Addresses, StatusAddr},
+    scope::{Scope, ScopePurpose},
 };
 use ipnet::IpNet;
-use libipam::types::vlan::VlanId;
+use libipam::{
+    services::{
+        allocation::Strategy,
+        capacity::Level,
+        ipam::{Fit, HostRequirement},
+    },
+    types::{host_count::HostCount, vlan::VlanId},
+};
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use uuid::Uuid;
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -34,6 +50,17 @@ pub struct NetworkCreateEntry {
     pub description: Option<String>,
     pub vlan: Option<VlanId>,
     pub kind: Option<Kind>,
+
+    /// Only the root networks are created with a vrf, the subnets belong to the vrf of its father
+    pub vrf: Option<Uuid>,
+
+    #[serde(default)]
+    pub custom_fields: CustomFields,
+    pub warning_threshold: Option<i16>,
+    pub critical_threshold: Option<i16>,
+    pub reserve_first: Option<i16>,
+    pub reserve_last: Option<i16>,
+    pub allocation: Option<Strategy>,
 }
 
 impl From<NetworkCreateEntry> for Network {
@@ -42,13 +69,21 @@ impl From<NetworkCreateEntry> for Network {
             id: Uuid::new_v4(),
             subnet: value.subnet,
             description: value.description,
-            used: 0.try_into().unwrap(),
+            used: HostCount::default(),
             free: value.subnet.into(),
             vlan: value.vlan,
             father: None,
             children: 0,
             status: StatusNetwork::default(),
-            kind: Kind::default(),
+            kind: value.kind.unwrap_or_default(),
+            vrf: value.vrf,
+            custom_fields: value.custom_fields,
+            warning_threshold: value.warning_threshold,
+            critical_threshold: value.critical_threshold,
+            capacity: Level::default(),
+            reserve_first: value.reserve_first,
+            reserve_last: value.reserve_last,
+            allocation: value.allocation.unwrap_or_default(),
         }
     }
 }
@@ -63,6 +98,9 @@ pub struct NodeCreateEntry {
     pub network_id: Option<uuid::Uuid>,
     pub username: Option<String>,
     pub pasword: Option<String>,
+
+    #[serde(default)]
+    pub custom_fields: CustomFields,
 }
 
 impl From<NodeCreateEntry> for Node {
@@ -76,6 +114,7 @@ impl From<NodeCreateEntry> for Node {
             mount_point: value.mount_point,
             username: value.username,
             password: value.pasword,
+            custom_fields: value.custom_fields,
         }
     }
 }
@@ -86,6 +125,10 @@ pub struct AddrCrateEntry {
     pub network_id: Uuid,
     pub status: Option<StatusAddr>,
     pub node_id: Option<Uuid>,
+
+    #[serde(default)]
+    pub custom_fields: CustomFields,
+    pub label: Option<String>,
 }
 
 impl From<AddrCrateEntry> for Addresses {
@@ -95,14 +138,177 @@ impl From<AddrCrateEntry> for Addresses {
             network_id: value.network_id,
             status: value.status.unwrap_or_default(),
             node_id: value.node_id,
+            custom_fields: value.custom_fields,
+            label: value.label,
         }
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AddrAllocateEntry {
+    pub count: Option<usize>,
+    pub node_id: Option<Uuid>,
+    pub status: Option<StatusAddr>,
+
+    /// The addresses of a pool are only allocated inside one of its scopes
+    pub scope: Option<Uuid>,
+
+    /// The strategy of the network is used by default
+    pub allocation: Option<Strategy>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubnetAllocateEntry {
+    pub prefix: u8,
+    pub description: Option<String>,
+    pub vlan: Option<VlanId>,
+    pub status: Option<StatusNetwork>,
+    pub kind: Option<Kind>,
+    pub fit: Option<Fit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VlsmEntry {
+    pub requirements: Vec<HostRequirement>,
+    pub commit: Option<bool>,
+    pub status: Option<StatusNetwork>,
+    pub kind: Option<Kind>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SplitEntry {
+    /// Prefix of the new networks, the network is split in two halves by default
+    pub prefix: Option<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResizeEntry {
+    pub prefix: u8,
+
+    /// Address of the resized network, the network address is kept by default
+    pub ip: Option<IpAddr>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeEntry {
+    /// Adjacent children to merge, every child is merged into the father if it's `None`
+    pub children: Option<Vec<Uuid>>,
+
+    /// Description of the supernet, the one of the first child if it's `None`
+    pub description: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateSubnet {
     pub prefix: u8,
     pub status: Option<StatusNetwork>,
     pub kind: Option<Kind>,
     pub description: Option<String>,
+
+    /// The subnets use the reservation policy of the father by default
+    pub reserve_first: Option<i16>,
+    pub reserve_last: Option<i16>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VrfCreateEntry {
+    pub name: String,
+    pub rd: Option<String>,
+    pub description: Option<String>,
+}
+
+impl From<VrfCreateEntry> for Vrf {
+    fn from(value: VrfCreateEntry) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: value.name,
+            rd: value.rd,
+            description: value.description,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CustomFieldCreateEntry {
+    pub name: String,
+    pub kind: FieldKind,
+    pub target: FieldTarget,
+    pub required: Option<bool>,
+    pub choices: Option<Vec<String>>,
+    pub description: Option<String>,
+}
+
+impl From<CustomFieldCreateEntry> for CustomField {
+    fn from(value: CustomFieldCreateEntry) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: value.name,
+            kind: value.kind,
+            target: value.target,
+            required: value.required.unwrap_or_default(),
+            choices: value.choices,
+            description: value.description,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagCreateEntry {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+impl From<TagCreateEntry> for Tag {
+    fn from(value: TagCreateEntry) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: value.name,
+            color: value.color,
+        }
+    }
+}
+
+/// Object that a tag is attached to, as `{"network": "<id>"}`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagObject {
+    Network(Uuid),
+    Address { ip: IpNet, network_id: Uuid },
+    Node(Uuid),
+    Vlan(VlanId),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScopeCreateEntry {
+    pub start_ip: IpAddr,
+    pub end_ip: IpAddr,
+    pub purpose: ScopePurpose,
+    pub description: Option<String>,
+}
+
+impl ScopeCreateEntry {
+    pub fn scope(self, network_id: Uuid) -> Scope {
+        Scope {
+            id: Uuid::new_v4(),
+            network_id,
+            start_ip: self.start_ip,
+            end_ip: self.end_ip,
+            purpose: self.purpose,
+            description: self.description,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusEntry {
+    pub status: StatusNetwork,
+    pub reason: Option<String>,
+}
+
+/// Same transition for several networks, none of them changes if one of them cannot
+#[derive(Debug, Deserialize)]
+pub struct StatusBulkEntry {
+    pub networks: Vec<Uuid>,
+    pub status: StatusNetwork,
+    pub reason: Option<String>,
 }
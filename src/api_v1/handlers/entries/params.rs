@@ -1,10 +1,15 @@
 use ipnet::IpNet;
+use libipam::services::capacity::Level;
 use macros::MapQuery as MapQueryDerive;
-use serde::Deserialize;
-use std::fmt::Debug;
+use serde::{Deserialize, Deserializer, de::Error as _};
+use std::{fmt::Debug, net::IpAddr};
 use uuid::Uuid;
 
-use crate::models::network::addresses::StatusAddr;
+use crate::models::{
+    custom_field::CustomFieldsFilter,
+    network::{Kind, StatusNetwork, addresses::StatusAddr},
+    tag::{TagFilter, TagJoin},
+};
 
 #[derive(Deserialize, Debug)]
 pub struct PaginationParams {
@@ -17,6 +22,76 @@ pub struct ParamNetwork {
     pub network: Option<IpNet>,
     pub id: Option<Uuid>,
     pub father: Option<Uuid>,
+    pub vrf: Option<Uuid>,
+    pub status: Option<StatusNetwork>,
+
+    #[serde(flatten, deserialize_with = "CustomFieldsFilter::from_query")]
+    pub custom_fields: Option<CustomFieldsFilter>,
+
+    #[serde(skip)]
+    pub tags: Option<TagFilter>,
+}
+
+/// Tags of the query as `tag=prod&tag=dmz`, with `tag_match=all` the objects need every tag
+#[derive(Debug, Default)]
+pub struct ParamTags {
+    pub names: Vec<String>,
+    pub all: bool,
+}
+
+impl ParamTags {
+    pub fn filter(self, join: TagJoin) -> Option<TagFilter> {
+        (!self.names.is_empty()).then_some(TagFilter {
+            names: self.names,
+            all: self.all,
+            join,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for ParamTags {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut resp = Self::default();
+
+        for (key, value) in Vec::<(String, String)>::deserialize(deserializer)? {
+            match key.as_str() {
+                "tag" if !resp.names.contains(&value) => resp.names.push(value),
+                "tag_match" => {
+                    resp.all = match value.as_str() {
+                        "any" => false,
+                        "all" => true,
+                        _ => return Err(D::Error::custom("tag_match must be any or all")),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(resp)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParamDelete {
+    pub dry_run: Option<bool>,
+    pub force: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParamRecount {
+    /// The drift is only reported without it
+    pub fix: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParamTree {
+    pub root: Option<Uuid>,
+    pub depth: Option<u32>,
+    pub status: Option<StatusNetwork>,
+    pub kind: Option<Kind>,
 }
 
 #[derive(Debug, Default, MapQueryDerive, Deserialize)]
@@ -24,10 +99,32 @@ pub struct ParamAddrFilter {
     pub ip: Option<IpNet>,
     pub node_id: Option<Uuid>,
     pub status: Option<StatusAddr>,
-    pub sort: Option<bool>,
+
+    #[serde(flatten, deserialize_with = "CustomFieldsFilter::from_query")]
+    pub custom_fields: Option<CustomFieldsFilter>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParamLookup {
+    pub ip: IpAddr,
+
+    /// The address is searched in the global vrf by default
+    pub vrf: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParamRange {
+    pub start: IpAddr,
+    pub end: IpAddr,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct IpNetParamNonOption {
     pub ip: IpNet,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct ParamCapacity {
+    /// Lowest level of the listed networks, the warning level by default
+    pub level: Option<Level>,
+}
@@ -20,6 +20,10 @@ impl From<RepositoryError> for ResponseError {
             RepositoryError::UpdaterEmpty => builder
                 .detail(value.to_string())
                 .status(StatusCode::BAD_REQUEST),
+            RepositoryError::Conflict(e) => builder
+                .status(StatusCode::CONFLICT)
+                .title("Conflict with an existing row".to_string())
+                .detail(e),
         };
 
         builder.build()
@@ -1,4 +1,4 @@
-use super::{ResponseError, Role};
+use super::{ResponseError, Role, Uuid};
 use axum::{extract::FromRequestParts, http::request::Parts};
 
 pub struct IsAdministrator;
@@ -22,3 +22,23 @@ where
         resp.await
     }
 }
+
+/// User of the token of the request
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentUser(pub Uuid);
+
+impl<S> FromRequestParts<S> for CurrentUser
+where
+    S: Send + Sync,
+{
+    type Rejection = ResponseError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Self>().copied().ok_or_else(|| {
+            ResponseError::unauthorized(
+                Some(parts.uri.to_string()),
+                Some("The request doesn't have a user".to_string()),
+            )
+        })
+    }
+}
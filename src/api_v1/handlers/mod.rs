@@ -1,11 +1,18 @@
 pub mod addresses;
 pub mod auth;
+pub mod calculator;
+pub mod capacity;
+pub mod custom_field;
 mod entries;
 pub mod error;
 pub mod extractors;
 pub mod network;
 pub mod node;
+pub mod scope;
+pub mod status;
+pub mod tag;
 pub mod vlan;
+pub mod vrf;
 
 use crate::{
     app_state::StateType,
@@ -1,19 +1,53 @@
 use crate::{
-    database::transaction::Transaction as _,
-    models::network::{DefaultValuesNetwork, NetwCondition},
+    database::{
+        repository::{IpOperation, Nullable, Table as _, error::RepositoryError},
+        transaction::{BuilderPgTransaction, Transaction as _},
+    },
+    models::{
+        capacity::{CapacityAlert, CapacityAlertCondition},
+        custom_field::FieldTarget,
+        network::{
+            DefaultValuesNetwork, DeleteImpact, Lookup, NetwCondition,
+            addresses::{AddrCondition, AddrIpCondition, AddrMode, Addresses, StatusAddr},
+            host_count::HostCountDrift,
+            scope::{Scope, ScopeCondition},
+            status::{StatusTransition, StatusTransitionCondition},
+            tree::NetworkTree,
+        },
+        tag::{AddressTag, AddressTagCondition, NetworkTag, NetworkTagCondition, TagJoin},
+        vrf::{Vrf, VrfCondition},
+    },
     response::ResponseQuery,
+    services,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
 };
-use std::net::{Ipv4Addr, Ipv6Addr};
 
 use super::{
     BATCH_SIZE, IsAdministrator, Json, PaginationParams, Path, Query, QueryResult, Repository,
     ResponseDefault, ResponseError, State, StateType, StatusCode, Uuid,
-    addresses::update_host_count,
-    entries::{self, models::CreateSubnet},
+    addresses::{policy_reserved, update_host_count},
+    capacity, custom_field,
+    entries::{
+        self,
+        models::{
+            CreateSubnet, MergeEntry, ResizeEntry, SplitEntry, SubnetAllocateEntry, VlsmEntry,
+        },
+    },
     models,
 };
 
-use entries::{models::NetworkCreateEntry, params::ParamNetwork};
+use entries::{
+    models::NetworkCreateEntry,
+    params::{ParamDelete, ParamLookup, ParamNetwork, ParamRecount, ParamTags, ParamTree},
+};
+use ipnet::IpNet;
+use libipam::services::{
+    ipam::{SubnetList, find_free_subnet, free_blocks, ip_to_u128, vlsm as vlsm_plan},
+    reservation::ReservePolicy,
+};
 use models::network::{Network, UpdateNetwork};
 use serde_json::json;
 
@@ -49,15 +83,177 @@ pub async fn create(
         }
     }
 
-    network.subnet = ipnet::IpNet::new(net, network.subnet.prefix_len()).unwrap();
-    Ok(state.insert::<Network>(network.into()).await?.into())
+    network.subnet = IpNet::new(net, network.subnet.prefix_len()).unwrap();
+
+    if let Some(vrf) = network.vrf {
+        state.get_one::<Vrf>(VrfCondition::p_key(vrf)).await?;
+    }
+
+    custom_field::validate(&state, FieldTarget::Network, &network.custom_fields).await?;
+    capacity::validate(
+        &state,
+        network.warning_threshold,
+        network.critical_threshold,
+    )?;
+    validate_policy(&state, network.reserve_first, network.reserve_last)?;
+
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        let father = lock_father(&mut transaction, network.subnet, network.vrf).await?;
+        let mut network = Network::from(network);
+        network.father = father.as_ref().map(|x| x.id);
+
+        let reserved = network.reserved(state.reserve_policy());
+        let n = reserved.len() as u128;
+        network.set_used(n);
+
+        let resp = transaction.insert(network).await?;
+
+        for addrs in reserved.chunks(BATCH_SIZE / Addresses::columns().len()) {
+            transaction.insert_many(addrs.to_vec()).await?;
+        }
+
+        if let Some(father) = father {
+            transaction
+                .update::<Network, _, _>(father.update_children(1), NetwCondition::p_key(father.id))
+                .await?;
+
+            update_host_count(&mut transaction, father, |x| {
+                x.less_free_more_used(1 + n);
+            })
+            .await?;
+        }
+
+        Ok(resp)
+    }
+    .await;
+
+    let resp = match resp {
+        Ok(e) => e,
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    transaction.commit().await?;
+
+    Ok(resp.into())
+}
+
+/// Returns the tightest father of `subnet` locked, `None` for a root network.
+///
+/// The overlaps are read again once the father is locked, so the networks created inside it at
+/// the same time are seen. The sibling roots are left to the `networks_overlap` constraint
+async fn lock_father(
+    transaction: &mut BuilderPgTransaction<'_>,
+    subnet: IpNet,
+    vrf: Option<Uuid>,
+) -> Result<Option<Network>, ResponseError> {
+    let mut locked = None;
+
+    loop {
+        let networks = transaction
+            .get::<Network>(
+                NetwCondition {
+                    subnet: Some(IpOperation::Overlaps(subnet)),
+                    vrf: Some(Nullable(vrf)),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?;
+
+        let father = tightest_father(subnet, &networks, None)?.cloned();
+
+        if father.as_ref().map(|x| x.id) == locked {
+            return Ok(father);
+        }
+
+        if let Some(father) = &father {
+            transaction
+                .get_for_update::<Network>(NetwCondition::p_key(father.id))
+                .await?;
+        }
+
+        locked = father.map(|x| x.id);
+    }
+}
+
+/// Splits `networks` in the network `root` with all its descendants, and the rest of networks
+fn subtree(networks: Vec<Network>, root: Uuid) -> (Vec<Network>, Vec<Network>) {
+    let mut children = HashMap::<Option<Uuid>, Vec<Network>>::new();
+    let mut resp = Vec::new();
+
+    for network in networks {
+        if network.id == root {
+            resp.push(network);
+        } else {
+            children.entry(network.father).or_default().push(network);
+        }
+    }
+
+    let mut pos = 0;
+
+    while let Some(network) = resp.get(pos) {
+        if let Some(subnets) = children.remove(&Some(network.id)) {
+            resp.extend(subnets);
+        }
+        pos += 1;
+    }
+
+    (resp, children.into_values().flatten().collect())
+}
+
+/// Returns the network with the longest prefix that contains `subnet`.
+///
+/// A network equal to `subnet` or contained in it is a conflict, the network `ignore` isn't compared
+fn tightest_father(
+    subnet: IpNet,
+    networks: &[Network],
+    ignore: Option<Uuid>,
+) -> Result<Option<&Network>, ResponseError> {
+    let mut father: Option<&Network> = None;
+    let networks = networks.iter().filter(|x| Some(x.id) != ignore);
+
+    if networks.clone().any(|x| x.subnet == subnet) {
+        return Err(ResponseError::builder()
+            .title("Network already exists".to_string())
+            .detail(format!("The network {subnet} already exists"))
+            .status(StatusCode::CONFLICT)
+            .build());
+    }
+
+    for network in networks {
+        if subnet.contains(&network.subnet) {
+            return Err(ResponseError::builder()
+                .title("Network overlap".to_string())
+                .detail(format!(
+                    "The network {subnet} overlaps with the network {}",
+                    network.subnet
+                ))
+                .status(StatusCode::CONFLICT)
+                .build());
+        } else if network.subnet.contains(&subnet)
+            && father.is_none_or(|x| x.subnet.prefix_len() < network.subnet.prefix_len())
+        {
+            father = Some(network);
+        }
+    }
+
+    Ok(father)
 }
 
 pub async fn get(
     State(state): State<StateType>,
-    Query(param): Query<ParamNetwork>,
+    Query(mut param): Query<ParamNetwork>,
+    Query(tags): Query<ParamTags>,
     Query(PaginationParams { offset, limit }): Query<PaginationParams>,
 ) -> ResponseDefault<Vec<Network>> {
+    param.tags = tags.filter(TagJoin::NETWORK);
+
     let data = state.get::<Network>(param, limit, offset).await?;
 
     let metadata = Some(json!({
@@ -74,16 +270,236 @@ pub async fn get(
     ))
 }
 
+pub async fn tree(
+    State(state): State<StateType>,
+    Query(ParamTree {
+        root,
+        depth,
+        status,
+        kind,
+    }): Query<ParamTree>,
+) -> ResponseDefault<Vec<NetworkTree>> {
+    // With a root only the networks inside it are read, the root included
+    let condition = match root {
+        Some(root) => {
+            let root = state.get_one::<Network>(NetwCondition::p_key(root)).await?;
+
+            NetwCondition {
+                subnet: Some(IpOperation::ContainedBy(root.subnet)),
+                vrf: Some(Nullable(root.vrf)),
+                ..Default::default()
+            }
+        }
+        None => NetwCondition::default(),
+    };
+
+    let networks = match state.get::<Network>(condition, None, None).await {
+        Ok(e) => e,
+        Err(RepositoryError::RowNotFound) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let data = NetworkTree::build(networks, root, depth, |x| {
+        status.is_none_or(|status| status == x.status) && kind.is_none_or(|kind| kind == x.kind)
+    });
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// Finds the network with the longest prefix that contains the address
+pub async fn lookup(
+    State(state): State<StateType>,
+    Query(ParamLookup { ip, vrf }): Query<ParamLookup>,
+) -> ResponseDefault<Lookup> {
+    let networks = match state
+        .get::<Network>(
+            NetwCondition {
+                subnet: Some(IpOperation::Contains(ip.into())),
+                vrf: Some(Nullable(vrf)),
+                ..Default::default()
+            },
+            None,
+            None,
+        )
+        .await
+    {
+        Ok(e) => e,
+        Err(RepositoryError::RowNotFound) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let network = networks
+        .iter()
+        .max_by_key(|x| x.subnet.prefix_len())
+        .cloned()
+        .ok_or(
+            ResponseError::builder()
+                .title("Network not found".to_string())
+                .detail(format!("There isn't a network that contains {ip}"))
+                .status(StatusCode::NOT_FOUND)
+                .build(),
+        )?;
+
+    let mut networks = networks
+        .into_iter()
+        .map(|x| (x.id, x))
+        .collect::<HashMap<_, _>>();
+
+    let mut ancestors = Vec::new();
+    let mut father = network.father;
+
+    while let Some(network) = father.and_then(|x| networks.remove(&x)) {
+        father = network.father;
+        ancestors.push(network);
+    }
+
+    let address = match state
+        .get_one::<Addresses>(AddrCondition::p_key(
+            IpNet::new(ip, network.subnet.prefix_len()).unwrap(),
+            network.id,
+        ))
+        .await
+    {
+        Ok(e) => Some(e),
+        Err(RepositoryError::RowNotFound) => {
+            let range = network.addresses();
+            range.index_of(ip).and_then(|x| range.get(x))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(ResponseQuery::new(
+        Some(Lookup {
+            network,
+            ancestors,
+            address,
+        }),
+        None,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// Networks whose `used`, `free` and `children` aren't the counters of their subnets and
+/// addresses, they're written again with `fix`
+pub async fn recount(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Query(ParamRecount { fix }): Query<ParamRecount>,
+) -> ResponseDefault<Vec<HostCountDrift>> {
+    let fix = fix.unwrap_or_default();
+
+    let _permit = state.heavy_task().acquire().await;
+
+    let data = services::host_count::check(&state, fix).await?;
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "fixed": fix,
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// Blocks of the network that aren't covered by any child
+pub async fn free(
+    State(state): State<StateType>,
+    Path(id): Path<Uuid>,
+) -> ResponseDefault<Vec<IpNet>> {
+    let network = state.get_one::<Network>(NetwCondition::p_key(id)).await?;
+
+    let children = match state
+        .get::<Network>(
+            NetwCondition {
+                father: Some(id),
+                ..Default::default()
+            },
+            None,
+            None,
+        )
+        .await
+    {
+        Ok(e) => e.into_iter().map(|x| x.subnet).collect::<Vec<_>>(),
+        Err(RepositoryError::RowNotFound) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let data = free_blocks(network.subnet, &children);
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
 pub async fn update(
     State(state): State<StateType>,
     _: IsAdministrator,
     Path(id): Path<Uuid>,
     Json(updater): Json<UpdateNetwork>,
 ) -> ResponseDefault<()> {
-    if updater.network.is_some() {
-        let old = state.get_one::<Network>(NetwCondition::p_key(id)).await?;
+    if let Some(custom_fields) = &updater.custom_fields {
+        custom_field::validate(&state, FieldTarget::Network, custom_fields).await?;
+    }
+
+    // A threshold is checked with the other threshold of the network
+    if updater.warning_threshold.is_some() || updater.critical_threshold.is_some() {
+        let network = state.get_one::<Network>(NetwCondition::p_key(id)).await?;
+
+        capacity::validate(
+            &state,
+            updater.warning_threshold.or(network.warning_threshold),
+            updater.critical_threshold.or(network.critical_threshold),
+        )?;
+    }
+
+    validate_policy(&state, updater.reserve_first, updater.reserve_last)?;
 
-        if old.children != 0 || old.used.as_i32() != 0 {
+    let Some(subnet) = updater.network else {
+        let resp = state
+            .update::<Network, _>(updater, NetwCondition::p_key(id))
+            .await?;
+
+        return Ok(resp.into());
+    };
+
+    let subnet = subnet.trunc();
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        let old = transaction
+            .get_for_update::<Network>(NetwCondition::p_key(id))
+            .await?;
+
+        let policy = policy_reserved(&mut transaction, id).await?;
+
+        if old.children != 0 || old.used != policy.len() as u128 {
             tracing::debug!("The network {:?} have subnets", old.subnet);
 
             return Err(ResponseError::builder()
@@ -91,34 +507,256 @@ pub async fn update(
                 .status(StatusCode::BAD_REQUEST)
                 .build());
         }
+
+        // The father is locked before the overlaps are read, as in `create`
+        if let Some(father) = old.father {
+            transaction
+                .get_for_update::<Network>(NetwCondition::p_key(father))
+                .await?;
+        }
+
+        let networks = transaction
+            .get::<Network>(
+                NetwCondition {
+                    subnet: Some(IpOperation::Overlaps(subnet)),
+                    vrf: Some(Nullable(old.vrf)),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?;
+
+        let father = tightest_father(subnet, &networks, Some(id))?.map(|x| x.id);
+
+        if father != old.father {
+            return Err(ResponseError::builder()
+                .title("Network overlap".to_string())
+                .detail(format!(
+                    "The network {subnet} doesn't belong to the same father as {}",
+                    old.subnet
+                ))
+                .status(StatusCode::CONFLICT)
+                .build());
+        }
+
+        // The reservations of the old subnet are replaced with the ones of the new subnet
+        for addr in &policy {
+            transaction
+                .delete::<Addresses, _>(AddrCondition::p_key(addr.ip, id))
+                .await?;
+        }
+
+        let mut addresses = transaction
+            .get::<Addresses>(
+                AddrCondition {
+                    network_id: Some(id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?;
+        let stored = addresses
+            .iter()
+            .map(|x| (x.ip.addr(), x.ip))
+            .collect::<HashMap<_, _>>();
+
+        let mut network = [Network {
+            subnet,
+            reserve_first: updater.reserve_first.or(old.reserve_first),
+            reserve_last: updater.reserve_last.or(old.reserve_last),
+            ..old.clone()
+        }];
+        network[0].set_used(0);
+
+        let reserved = reserve_addresses(&mut network, state.reserve_policy(), &mut addresses);
+
+        let resp = transaction
+            .update::<Network, _, _>(network[0].update_subnet(subnet), NetwCondition::p_key(id))
+            .await?;
+
+        transaction
+            .update::<Network, _, _>(network[0].update_host_count(), NetwCondition::p_key(id))
+            .await?;
+
+        // The reserved addresses replace the unknown addresses stored in their position
+        let addresses = addresses
+            .into_iter()
+            .filter(Addresses::is_policy_reserved)
+            .collect::<Vec<_>>();
+
+        for ip in addresses.iter().filter_map(|x| stored.get(&x.ip.addr())) {
+            transaction
+                .delete::<Addresses, _>(AddrCondition::p_key(*ip, id))
+                .await?;
+        }
+
+        for addrs in addresses.chunks(BATCH_SIZE / Addresses::columns().len()) {
+            transaction.insert_many(addrs.to_vec()).await?;
+        }
+
+        update_reserved(&mut transaction, old.father, policy.len() as u128, reserved).await?;
+
+        if updater.description.is_some()
+            || updater.vlan.is_some()
+            || updater.custom_fields.is_some()
+            || updater.warning_threshold.is_some()
+            || updater.critical_threshold.is_some()
+            || updater.reserve_first.is_some()
+            || updater.reserve_last.is_some()
+            || updater.allocation.is_some()
+        {
+            transaction
+                .update::<Network, _, _>(updater, NetwCondition::p_key(id))
+                .await?;
+        }
+
+        Ok(resp)
     }
+    .await;
 
-    let resp = state
-        .update::<Network, _>(updater, NetwCondition::p_key(id))
-        .await?;
+    let resp = match resp {
+        Ok(e) => e,
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    transaction.commit().await?;
 
     Ok(resp.into())
 }
 
+/// Deletes the network with its subnets and addresses, the counters of the ancestors are updated.
+///
+/// With `dry_run` nothing is deleted, and a network with subnets or used addresses is only
+/// deleted with `force`. Only the addresses with status or node are listed, the unknown addresses
+/// of the dense mode are placeholders
 pub async fn delete(
     State(state): State<StateType>,
     _: IsAdministrator,
     Path(id): Path<Uuid>,
-) -> ResponseDefault<()> {
+    Query(ParamDelete { dry_run, force }): Query<ParamDelete>,
+) -> ResponseDefault<DeleteImpact> {
     tracing::debug!("delete one network: {}", id);
 
-    let for_delete = state.get_one::<Network>(NetwCondition::p_key(id)).await?;
-
+    let dry_run = dry_run.unwrap_or_default();
     let mut transaction = state.transaction().await?;
 
-    let network_used = for_delete.used.as_i32();
+    let resp = async {
+        let for_delete = transaction
+            .get_for_update::<Network>(NetwCondition::p_key(id))
+            .await?;
 
-    update_host_count(&mut transaction, for_delete, |x| {
-        x.less_used_more_free(network_used);
-    })
-    .await?;
+        // The addresses reserved by the policy don't put the network in use
+        let reserved = policy_reserved(&mut transaction, id).await?.len() as u128;
+
+        if !dry_run
+            && !force.unwrap_or_default()
+            && (for_delete.children != 0 || for_delete.used != reserved)
+        {
+            return Err(ResponseError::builder()
+                .title("Network not empty".to_string())
+                .detail(format!(
+                    "The network {} has subnets or used addresses, use force to delete it",
+                    for_delete.subnet
+                ))
+                .status(StatusCode::CONFLICT)
+                .build());
+        }
+
+        let (networks, _) = subtree(
+            transaction
+                .get::<Network>(
+                    NetwCondition {
+                        subnet: Some(IpOperation::ContainedBy(for_delete.subnet)),
+                        vrf: Some(Nullable(for_delete.vrf)),
+                        ..Default::default()
+                    },
+                    None,
+                    None,
+                )
+                .await?,
+            for_delete.id,
+        );
+
+        let ids = networks.iter().map(|x| x.id).collect::<HashSet<_>>();
+        let addresses = transaction
+            .get::<Addresses>(
+                AddrIpCondition {
+                    ip: Some(IpOperation::ContainedBy(for_delete.subnet)),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+            .into_iter()
+            .filter(|x| ids.contains(&x.network_id) && x.is_stored(AddrMode::Sparse))
+            .collect::<Vec<_>>();
+
+        if !dry_run {
+            transaction
+                .delete::<Network, _>(NetwCondition::p_key(for_delete.id))
+                .await?;
+
+            if let Some(father) = for_delete.father {
+                let father = transaction
+                    .get_for_update::<Network>(NetwCondition::p_key(father))
+                    .await?;
+
+                transaction
+                    .update::<Network, _, _>(
+                        father.update_children(-1),
+                        NetwCondition::p_key(father.id),
+                    )
+                    .await?;
+
+                let used = for_delete.used.as_u128().saturating_add(1);
 
-    Ok(QueryResult::new(1).into())
+                update_host_count(&mut transaction, father, |x| {
+                    x.less_used_more_free(used);
+                })
+                .await?;
+            }
+        }
+
+        Ok(DeleteImpact {
+            networks,
+            addresses,
+        })
+    }
+    .await;
+
+    let impact = match resp {
+        Ok(e) if dry_run => {
+            transaction.rollback().await?;
+            e
+        }
+        Ok(e) => {
+            transaction.commit().await?;
+            e
+        }
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    let metadata = Some(json!({
+        "networks": impact.networks.len(),
+        "addresses": impact.addresses.len(),
+        "dry_run": dry_run,
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(impact),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
 }
 
 pub async fn subnetting(
@@ -130,8 +768,12 @@ pub async fn subnetting(
         status,
         kind,
         description,
+        reserve_first,
+        reserve_last,
     }): Json<CreateSubnet>,
 ) -> ResponseDefault<()> {
+    validate_policy(&state, reserve_first, reserve_last)?;
+
     let father = state
         .get_one::<Network>(NetwCondition::p_key(father))
         .await?;
@@ -142,20 +784,44 @@ pub async fn subnetting(
             .status(StatusCode::BAD_REQUEST)
     })?;
 
-    subnet.set_default_values(DefaultValuesNetwork::new(
-        father.id,
-        status,
-        kind,
-        description,
-    ));
+    subnet.set_default_values(DefaultValuesNetwork {
+        vrf: father.vrf,
+        reserve_first: reserve_first.or(father.reserve_first),
+        reserve_last: reserve_last.or(father.reserve_last),
+        ..DefaultValuesNetwork::new(father.id, status, kind, description)
+    });
 
     let _permit = state.heavy_task().acquire().await;
 
     let mut transaction = state.transaction().await?;
     let len = subnet.len();
+    let children = father.update_children(len.try_into().unwrap_or(i32::MAX));
+
+    if let Err(e) = transaction
+        .update::<Network, _, _>(children, NetwCondition::p_key(father.id))
+        .await
+    {
+        transaction.rollback().await?;
+        return Err(ResponseError::from(e));
+    }
+
+    // Every network binds one parameter per column and a query cannot exceed 65535 parameters
+    let window = BATCH_SIZE / Network::columns().len();
+    let mut reserved = 0;
+
+    for mut net in subnet.batch(window) {
+        match insert_subnets(&mut transaction, &mut net, state.reserve_policy()).await {
+            Ok(n) => reserved += n,
+            Err(e) => {
+                transaction.rollback().await?;
+                return Err(e);
+            }
+        }
+    }
 
+    // The father counts the new subnets and the addresses reserved inside them
     if let Err(e) = update_host_count(&mut transaction, father, |x| {
-        x.less_free_more_used(len.try_into().unwrap());
+        x.less_free_more_used(len as u128 + reserved);
     })
     .await
     {
@@ -163,23 +829,1021 @@ pub async fn subnetting(
         return Err(e);
     }
 
-    if len >= BATCH_SIZE {
-        let subnet = subnet.batch(BATCH_SIZE);
+    transaction.commit().await?;
 
-        for net in subnet {
-            if let Err(e) = transaction.insert_many(net).await {
-                transaction.rollback().await?;
-                return Err(ResponseError::from(e));
+    Ok(QueryResult::new(len as u64).into())
+}
+
+/// Inserts new subnets with the addresses reserved by their policy, returns the number of reserved addresses.
+///
+/// The used hosts of the subnets are updated with their reserved addresses
+async fn insert_subnets(
+    transaction: &mut BuilderPgTransaction<'_>,
+    networks: &mut [Network],
+    global: ReservePolicy,
+) -> Result<u128, ResponseError> {
+    let mut reserved = Vec::new();
+
+    for network in networks.iter_mut() {
+        let addrs = network.reserved(global);
+        network.set_used(addrs.len() as u128);
+        reserved.extend(addrs);
+    }
+
+    transaction.insert_many(networks.to_vec()).await?;
+
+    for addrs in reserved.chunks(BATCH_SIZE / Addresses::columns().len()) {
+        transaction.insert_many(addrs.to_vec()).await?;
+    }
+
+    Ok(reserved.len() as u128)
+}
+
+/// Adds the addresses reserved by the policy of the networks to `addresses` and counts them as
+/// used, returns the number of reserved addresses.
+///
+/// An address with a status, node or custom fields keeps its position, an unknown one is replaced
+fn reserve_addresses(
+    networks: &mut [Network],
+    global: ReservePolicy,
+    addresses: &mut Vec<Addresses>,
+) -> u128 {
+    let mut index = addresses
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x.ip.addr(), i))
+        .collect::<HashMap<_, _>>();
+    let mut total = 0;
+
+    for network in networks {
+        let mut n = 0;
+
+        for addr in network.reserved(global) {
+            match index.get(&addr.ip.addr()) {
+                Some(i) if addresses[*i].is_stored(AddrMode::Sparse) => continue,
+                Some(i) => addresses[*i] = addr,
+                None => {
+                    index.insert(addr.ip.addr(), addresses.len());
+                    addresses.push(addr);
+                }
+            }
+
+            n += 1;
+        }
+
+        network.set_used(network.used.as_u128() + n);
+        total += n;
+    }
+
+    total
+}
+
+/// Moves the change of the reserved addresses of a network to the counters of its ancestors
+async fn update_reserved(
+    transaction: &mut BuilderPgTransaction<'_>,
+    father: Option<Uuid>,
+    old: u128,
+    new: u128,
+) -> Result<(), ResponseError> {
+    let Some(father) = father.filter(|_| old != new) else {
+        return Ok(());
+    };
+
+    let father = transaction
+        .get_for_update::<Network>(NetwCondition::p_key(father))
+        .await?;
+
+    update_host_count(transaction, father, |x| {
+        x.less_used_more_free(old);
+        x.less_free_more_used(new);
+    })
+    .await
+}
+
+/// Checks the reservation policy of a network, the missing values are taken from the global policy
+fn validate_policy(
+    state: &StateType,
+    first: Option<i16>,
+    last: Option<i16>,
+) -> Result<(), ResponseError> {
+    state
+        .reserve_policy()
+        .with(first, last)
+        .map(|_| ())
+        .map_err(|e| {
+            ResponseError::builder()
+                .title("Invalid reservation policy".to_string())
+                .detail(e.to_string())
+                .status(StatusCode::BAD_REQUEST)
+                .build()
+        })
+}
+
+/// Creates one subnet in the first free block of the father that fits it, the existing children are skipped
+pub async fn allocate(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(father): Path<Uuid>,
+    Json(SubnetAllocateEntry {
+        prefix,
+        description,
+        vlan,
+        status,
+        kind,
+        fit,
+    }): Json<SubnetAllocateEntry>,
+) -> ResponseDefault<Network> {
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        let father = transaction
+            .get_for_update::<Network>(NetwCondition::p_key(father))
+            .await?;
+
+        let children = transaction
+            .get::<Network>(
+                NetwCondition {
+                    father: Some(father.id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+            .into_iter()
+            .map(|x| x.subnet)
+            .collect::<Vec<_>>();
+
+        let subnet = find_free_subnet(father.subnet, &children, prefix, fit.unwrap_or_default())
+            .map_err(|e| {
+                ResponseError::builder()
+                    .title("Subnet not allocated".to_string())
+                    .detail(e.to_string())
+                    .status(StatusCode::CONFLICT)
+                    .build()
+            })?;
+
+        let mut network = [Network {
+            vlan,
+            description,
+            father: Some(father.id),
+            status: status.unwrap_or_default(),
+            kind: kind.unwrap_or_default(),
+            vrf: father.vrf,
+            reserve_first: father.reserve_first,
+            reserve_last: father.reserve_last,
+            ..Network::from(subnet)
+        }];
+
+        let reserved =
+            insert_subnets(&mut transaction, &mut network, state.reserve_policy()).await?;
+
+        transaction
+            .update::<Network, _, _>(father.update_children(1), NetwCondition::p_key(father.id))
+            .await?;
+
+        update_host_count(&mut transaction, father, |x| {
+            x.less_free_more_used(1 + reserved);
+        })
+        .await?;
+
+        let [network] = network;
+        Ok(network)
+    }
+    .await;
+
+    let network = match resp {
+        Ok(e) => e,
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    transaction.commit().await?;
+
+    Ok(ResponseQuery::new(
+        Some(network),
+        None,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+pub async fn vlsm(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(father): Path<Uuid>,
+    Json(VlsmEntry {
+        requirements,
+        commit,
+        status,
+        kind,
+    }): Json<VlsmEntry>,
+) -> ResponseDefault<Vec<Network>> {
+    if requirements.is_empty() {
+        return Err(ResponseError::builder()
+            .title("Empty VLSM plan".to_string())
+            .detail("At least one host requirement is necessary".to_string())
+            .status(StatusCode::BAD_REQUEST)
+            .build());
+    }
+
+    // Every requirement is expanded to `count` subnets
+    let len = requirements
+        .iter()
+        .map(|x| x.count.unwrap_or(1))
+        .fold(0usize, usize::saturating_add);
+
+    if len > BATCH_SIZE {
+        return Err(ResponseError::builder()
+            .title("VLSM plan too big".to_string())
+            .detail(format!(
+                "The plan cannot have more than {BATCH_SIZE} subnets"
+            ))
+            .status(StatusCode::BAD_REQUEST)
+            .build());
+    }
+
+    let commit = commit.unwrap_or_default();
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        let father = transaction
+            .get_for_update::<Network>(NetwCondition::p_key(father))
+            .await?;
+
+        let children = transaction
+            .get::<Network>(
+                NetwCondition {
+                    father: Some(father.id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+            .into_iter()
+            .map(|x| x.subnet)
+            .collect::<Vec<_>>();
+
+        let plan = vlsm_plan(father.subnet, &children, requirements).map_err(|e| {
+            ResponseError::builder()
+                .title("VLSM plan not possible".to_string())
+                .detail(e.to_string())
+                .status(StatusCode::CONFLICT)
+                .build()
+        })?;
+
+        let mut networks = plan
+            .into_iter()
+            .map(|x| Network {
+                description: Some(x.name),
+                father: Some(father.id),
+                status: status.unwrap_or_default(),
+                kind: kind.unwrap_or_default(),
+                vrf: father.vrf,
+                reserve_first: father.reserve_first,
+                reserve_last: father.reserve_last,
+                ..Network::from(x.subnet)
+            })
+            .collect::<Vec<_>>();
+
+        if commit {
+            let len = networks.len();
+            let mut reserved = 0;
+
+            for window in networks.chunks_mut(BATCH_SIZE / Network::columns().len()) {
+                reserved +=
+                    insert_subnets(&mut transaction, window, state.reserve_policy()).await?;
+            }
+
+            transaction
+                .update::<Network, _, _>(
+                    father.update_children(len as i32),
+                    NetwCondition::p_key(father.id),
+                )
+                .await?;
+
+            update_host_count(&mut transaction, father, |x| {
+                x.less_free_more_used(len as u128 + reserved);
+            })
+            .await?;
+        }
+
+        Ok(networks)
+    }
+    .await;
+
+    let networks = match resp {
+        Ok(e) if commit => {
+            transaction.commit().await?;
+            e
+        }
+        Ok(e) => {
+            transaction.rollback().await?;
+            e
+        }
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    Ok(ResponseQuery::new(
+        Some(networks),
+        Some(json!({ "commit": commit })),
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// Merges children of the network into one supernet, or into the network itself.
+///
+/// The merged children cannot have used addresses or subnets
+pub async fn merge(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+    Json(MergeEntry {
+        children: selected,
+        description,
+    }): Json<MergeEntry>,
+) -> ResponseDefault<Network> {
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        let father = transaction
+            .get_for_update::<Network>(NetwCondition::p_key(id))
+            .await?;
+
+        let children = transaction
+            .get::<Network>(
+                NetwCondition {
+                    father: Some(father.id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?;
+
+        let merged = match &selected {
+            Some(selected) => {
+                if let Some(id) = selected
+                    .iter()
+                    .find(|id| !children.iter().any(|x| x.id == **id))
+                {
+                    return Err(ResponseError::builder()
+                        .title("Invalid child".to_string())
+                        .detail(format!(
+                            "The network {id} isn't a child of {}",
+                            father.subnet
+                        ))
+                        .status(StatusCode::BAD_REQUEST)
+                        .build());
+                }
+
+                children
+                    .into_iter()
+                    .filter(|x| selected.contains(&x.id))
+                    .collect::<Vec<_>>()
+            }
+            None => children,
+        };
+
+        if merged.is_empty() {
+            return Err(ResponseError::builder()
+                .title("Nothing to merge".to_string())
+                .detail(format!(
+                    "The network {} doesn't have children",
+                    father.subnet
+                ))
+                .status(StatusCode::BAD_REQUEST)
+                .build());
+        }
+
+        // The addresses reserved by the policy are counted as used, they're deleted with the child
+        let mut policy = 0;
+
+        for child in &merged {
+            let addresses = transaction
+                .get::<Addresses>(
+                    AddrCondition {
+                        network_id: Some(child.id),
+                        ..Default::default()
+                    },
+                    None,
+                    None,
+                )
+                .await?;
+
+            let reserved = addresses.iter().filter(|x| x.is_policy_reserved()).count() as u128;
+
+            if child.used != reserved || child.children != 0 {
+                return Err(ResponseError::builder()
+                    .title("Network not empty".to_string())
+                    .detail(format!(
+                        "The network {} has used addresses or subnets",
+                        child.subnet
+                    ))
+                    .status(StatusCode::CONFLICT)
+                    .build());
+            }
+
+            // The addresses with a node or custom fields aren't counted as used, but they're lost too
+            if addresses
+                .iter()
+                .any(|x| x.is_stored(AddrMode::Sparse) && !x.is_policy_reserved())
+            {
+                return Err(ResponseError::builder()
+                    .title("Network not empty".to_string())
+                    .detail(format!(
+                        "The network {} has addresses with a status, node or custom fields",
+                        child.subnet
+                    ))
+                    .status(StatusCode::CONFLICT)
+                    .build());
+            }
+
+            policy += reserved;
+        }
+
+        let supernet = selected
+            .map(|_| IpNet::aggregate(&merged.iter().map(|x| x.subnet).collect::<Vec<_>>()))
+            .unwrap_or_else(|| vec![father.subnet]);
+
+        let supernet = match supernet.as_slice() {
+            [supernet] if *supernet == father.subnet => None,
+            [supernet] => Some(*supernet),
+            _ => {
+                return Err(ResponseError::builder()
+                    .title("Networks not adjacent".to_string())
+                    .detail("The networks cannot be merged into one supernet".to_string())
+                    .status(StatusCode::BAD_REQUEST)
+                    .build());
+            }
+        };
+
+        for child in &merged {
+            transaction
+                .delete::<Network, _>(NetwCondition::p_key(child.id))
+                .await?;
+        }
+
+        let removed = merged.len() - usize::from(supernet.is_some());
+
+        let (network, reserved) = match supernet {
+            Some(subnet) => {
+                let mut network = [Network {
+                    description: description.or_else(|| merged[0].description.clone()),
+                    ..merged[0].with_subnet(subnet)
+                }];
+
+                let reserved =
+                    insert_subnets(&mut transaction, &mut network, state.reserve_policy()).await?;
+
+                let [network] = network;
+                (network, reserved)
+            }
+            None => (father.clone(), 0),
+        };
+
+        transaction
+            .update::<Network, _, _>(
+                father.update_children(-(removed as i32)),
+                NetwCondition::p_key(father.id),
+            )
+            .await?;
+
+        // The father loses the merged children with their reserved addresses, and counts the supernet ones
+        update_host_count(&mut transaction, father, |x| {
+            x.less_used_more_free(removed as u128 + policy);
+            x.less_free_more_used(reserved);
+        })
+        .await?;
+
+        if supernet.is_none() {
+            return Ok(transaction
+                .get_for_update::<Network>(NetwCondition::p_key(network.id))
+                .await?);
+        }
+
+        Ok(network)
+    }
+    .await;
+
+    let network = match resp {
+        Ok(e) => e,
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    transaction.commit().await?;
+
+    Ok(ResponseQuery::new(
+        Some(network),
+        None,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// Changes the prefix of a network that can have subnets and addresses.
+///
+/// The subnets and the addresses with status or node must be in the new network, the unknown
+/// addresses of the dense mode that aren't hosts of the new network are deleted
+pub async fn resize(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+    Json(ResizeEntry { prefix, ip }): Json<ResizeEntry>,
+) -> ResponseDefault<Network> {
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        let old = transaction
+            .get_for_update::<Network>(NetwCondition::p_key(id))
+            .await?;
+
+        let subnet = IpNet::new(ip.unwrap_or(old.subnet.network()), prefix)
+            .map(|x| x.trunc())
+            .ok()
+            .filter(|x| x.prefix_len() != old.subnet.prefix_len())
+            .filter(|x| x.contains(&old.subnet) || old.subnet.contains(x))
+            .ok_or(
+                ResponseError::builder()
+                    .title("Invalid prefix".to_string())
+                    .detail(format!(
+                        "The network {} cannot be resized to /{prefix}",
+                        old.subnet
+                    ))
+                    .status(StatusCode::BAD_REQUEST)
+                    .build(),
+            )?;
+
+        let (subtree, _) = subtree(
+            transaction
+                .get::<Network>(
+                    NetwCondition {
+                        subnet: Some(IpOperation::ContainedBy(old.subnet)),
+                        vrf: Some(Nullable(old.vrf)),
+                        ..Default::default()
+                    },
+                    None,
+                    None,
+                )
+                .await?,
+            id,
+        );
+
+        let others = transaction
+            .get::<Network>(
+                NetwCondition {
+                    subnet: Some(IpOperation::Overlaps(subnet)),
+                    vrf: Some(Nullable(old.vrf)),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+            .into_iter()
+            .filter(|x| !subtree.iter().any(|y| y.id == x.id))
+            .collect::<Vec<_>>();
+
+        if tightest_father(subnet, &others, None)?.map(|x| x.id) != old.father {
+            return Err(ResponseError::builder()
+                .title("Network overlap".to_string())
+                .detail(format!(
+                    "The network {subnet} doesn't fit in the father of {}",
+                    old.subnet
+                ))
+                .status(StatusCode::CONFLICT)
+                .build());
+        }
+
+        if let Some(child) = subtree
+            .iter()
+            .find(|x| x.father == Some(id) && !subnet.contains(&x.subnet))
+        {
+            return Err(ResponseError::builder()
+                .title("Network overlap".to_string())
+                .detail(format!(
+                    "The subnet {} doesn't fit in {subnet}",
+                    child.subnet
+                ))
+                .status(StatusCode::CONFLICT)
+                .build());
+        }
+
+        let mut network = [Network {
+            subnet,
+            ..old.clone()
+        }];
+        let range = network[0].addresses();
+
+        if let Some(scope) = transaction
+            .get::<Scope>(ScopeCondition::network(id), None, None)
+            .await?
+            .into_iter()
+            .find(|x| range.index_of(x.start_ip).is_none() || range.index_of(x.end_ip).is_none())
+        {
+            return Err(ResponseError::builder()
+                .title("Scope out of the network".to_string())
+                .detail(format!(
+                    "The scope from {} to {} isn't inside {subnet}",
+                    scope.start_ip, scope.end_ip
+                ))
+                .status(StatusCode::CONFLICT)
+                .build());
+        }
+
+        let mut addresses = Vec::new();
+        let mut policy = 0;
+
+        for addr in transaction
+            .get::<Addresses>(
+                AddrCondition {
+                    network_id: Some(id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+        {
+            // The reservations are applied again in the new subnet
+            if addr.is_policy_reserved() {
+                policy += 1;
+            } else if range.index_of(addr.ip.addr()).is_some() {
+                addresses.push(Addresses {
+                    ip: IpNet::new(addr.ip.addr(), prefix).unwrap(),
+                    ..addr
+                });
+            } else if addr.is_stored(AddrMode::Sparse) {
+                return Err(ResponseError::builder()
+                    .title("Address out of the network".to_string())
+                    .detail(format!(
+                        "The address {} isn't a host of {subnet}",
+                        addr.ip.addr()
+                    ))
+                    .status(StatusCode::CONFLICT)
+                    .build());
             }
         }
-    } else {
-        let subnet = subnet.collect::<Vec<_>>();
 
-        if let Err(e) = transaction.insert_many(subnet).await {
+        let hosts = addresses
+            .iter()
+            .map(|x| (x.ip.addr(), (x.ip, x.network_id)))
+            .collect::<HashMap<_, _>>();
+        let tags = address_tags(&mut transaction, id, &hosts).await?;
+
+        network[0].set_used(old.used.as_u128().saturating_sub(policy));
+        let reserved = reserve_addresses(&mut network, state.reserve_policy(), &mut addresses);
+
+        // The ip of the addresses has the prefix of the network
+        transaction
+            .delete::<Addresses, _>(AddrCondition {
+                network_id: Some(id),
+                ..Default::default()
+            })
+            .await?;
+
+        for window in addresses.chunks(BATCH_SIZE / Addresses::columns().len()) {
+            transaction.insert_many(window.to_vec()).await?;
+        }
+
+        for window in tags.chunks(BATCH_SIZE / AddressTag::columns().len()) {
+            transaction.insert_many(window.to_vec()).await?;
+        }
+
+        transaction
+            .update::<Network, _, _>(network[0].update_subnet(subnet), NetwCondition::p_key(id))
+            .await?;
+
+        transaction
+            .update::<Network, _, _>(network[0].update_host_count(), NetwCondition::p_key(id))
+            .await?;
+
+        update_reserved(&mut transaction, old.father, policy, reserved).await?;
+
+        Ok(transaction
+            .get_for_update::<Network>(NetwCondition::p_key(id))
+            .await?)
+    }
+    .await;
+
+    let network = match resp {
+        Ok(e) => e,
+        Err(e) => {
             transaction.rollback().await?;
-            return Err(ResponseError::from(e));
+            return Err(e);
         }
+    };
+
+    transaction.commit().await?;
+
+    Ok(ResponseQuery::new(
+        Some(network),
+        None,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// Tags of the addresses of a network, moved to the new address of each host.
+///
+/// The tags of the hosts without a new address are dropped
+async fn address_tags(
+    transaction: &mut BuilderPgTransaction<'_>,
+    network_id: Uuid,
+    hosts: &HashMap<IpAddr, (IpNet, Uuid)>,
+) -> Result<Vec<AddressTag>, RepositoryError> {
+    Ok(transaction
+        .get::<AddressTag>(
+            AddressTagCondition {
+                network_id: Some(network_id),
+                ..Default::default()
+            },
+            None,
+            None,
+        )
+        .await?
+        .into_iter()
+        .filter_map(|tag| {
+            let (ip, network_id) = hosts.get(&tag.ip.addr())?;
+
+            Some(AddressTag {
+                ip: *ip,
+                network_id: *network_id,
+                ..tag
+            })
+        })
+        .collect())
+}
+
+/// Replaces a network without subnets with the networks of the prefix, its addresses are moved
+/// to the new network that contains them.
+///
+/// The addresses with status or node must be hosts of the new networks, the unknown addresses of
+/// the dense mode that aren't hosts are deleted
+pub async fn split(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+    Json(SplitEntry { prefix }): Json<SplitEntry>,
+) -> ResponseDefault<Vec<Network>> {
+    let _permit = state.heavy_task().acquire().await;
+
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        let old = transaction
+            .get_for_update::<Network>(NetwCondition::p_key(id))
+            .await?;
+
+        if old.children != 0 {
+            return Err(ResponseError::builder()
+                .title("Network with subnets".to_string())
+                .detail(format!("The network {} has subnets", old.subnet))
+                .status(StatusCode::CONFLICT)
+                .build());
+        }
+
+        // The scopes would be deleted with the network
+        if !transaction
+            .get::<Scope>(ScopeCondition::network(id), None, None)
+            .await?
+            .is_empty()
+        {
+            return Err(ResponseError::builder()
+                .title("Network with scopes".to_string())
+                .detail(format!("The network {} has scopes", old.subnet))
+                .status(StatusCode::CONFLICT)
+                .build());
+        }
+
+        let prefix = prefix.unwrap_or(old.subnet.prefix_len().saturating_add(1));
+
+        let subnets = SubnetList::new(old.subnet, prefix).map_err(|e| {
+            ResponseError::builder()
+                .detail(e.to_string())
+                .status(StatusCode::BAD_REQUEST)
+                .build()
+        })?;
+
+        // Every new network is kept in memory with its addresses, as the plans of `vlsm`
+        if subnets.len() > BATCH_SIZE {
+            return Err(ResponseError::builder()
+                .title("Split too big".to_string())
+                .detail(format!(
+                    "The network cannot be split in more than {BATCH_SIZE} networks"
+                ))
+                .status(StatusCode::BAD_REQUEST)
+                .build());
+        }
+
+        let mut networks = subnets
+            .map(|subnet| old.with_subnet(subnet))
+            .collect::<Vec<_>>();
+
+        let start = ip_to_u128(old.subnet.network());
+        let host_bits = old.subnet.max_prefix_len() - prefix;
+        let mut used = vec![0u128; networks.len()];
+        let mut addresses = Vec::new();
+        let mut policy = 0;
+
+        for addr in transaction
+            .get::<Addresses>(
+                AddrCondition {
+                    network_id: Some(id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+        {
+            // The reservations are applied again in every new network
+            if addr.is_policy_reserved() {
+                policy += 1;
+                continue;
+            }
+
+            let ip = addr.ip.addr();
+            let pos = ip_to_u128(ip)
+                .checked_sub(start)
+                .and_then(|x| usize::try_from(x.checked_shr(host_bits.into())?).ok())
+                .filter(|pos| {
+                    networks
+                        .get(*pos)
+                        .is_some_and(|x| x.addresses().index_of(ip).is_some())
+                });
+
+            if let Some(pos) = pos {
+                if StatusAddr::USED.contains(&addr.status) {
+                    used[pos] += 1;
+                }
+
+                addresses.push(Addresses {
+                    ip: IpNet::new(ip, prefix).unwrap(),
+                    network_id: networks[pos].id,
+                    ..addr
+                });
+            } else if addr.is_stored(AddrMode::Sparse) {
+                return Err(ResponseError::builder()
+                    .title("Address out of the networks".to_string())
+                    .detail(format!("The address {ip} isn't a host of the new networks"))
+                    .status(StatusCode::CONFLICT)
+                    .build());
+            }
+        }
+
+        for (network, used) in networks.iter_mut().zip(used) {
+            network.set_used(used);
+        }
+
+        let reserved = reserve_addresses(&mut networks, state.reserve_policy(), &mut addresses);
+
+        let hosts = addresses
+            .iter()
+            .map(|x| (x.ip.addr(), (x.ip, x.network_id)))
+            .collect::<HashMap<_, _>>();
+        let tags = address_tags(&mut transaction, id, &hosts).await?;
+
+        // The new networks have the tags of the old one
+        let network_tags = transaction
+            .get::<NetworkTag>(
+                NetworkTagCondition {
+                    network_id: Some(id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+            .into_iter()
+            .flat_map(|tag| {
+                networks.iter().map(move |x| NetworkTag {
+                    network_id: x.id,
+                    ..tag
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Every new network keeps the status history and the capacity alerts of the old one
+        let history = transaction
+            .get::<StatusTransition>(
+                StatusTransitionCondition {
+                    network_id: Some(id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+            .into_iter()
+            .flat_map(|record| {
+                networks.iter().map(move |x| StatusTransition {
+                    id: Uuid::new_v4(),
+                    network_id: x.id,
+                    ..record.clone()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let alerts = transaction
+            .get::<CapacityAlert>(
+                CapacityAlertCondition {
+                    network_id: Some(id),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .await?
+            .into_iter()
+            .flat_map(|alert| {
+                networks.iter().map(move |x| CapacityAlert {
+                    id: Uuid::new_v4(),
+                    network_id: x.id,
+                    ..alert.clone()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // The addresses, their tags, the history and the alerts are deleted by cascade
+        transaction
+            .delete::<Network, _>(NetwCondition::p_key(id))
+            .await?;
+
+        for window in networks.chunks(BATCH_SIZE / Network::columns().len()) {
+            transaction.insert_many(window.to_vec()).await?;
+        }
+
+        for window in addresses.chunks(BATCH_SIZE / Addresses::columns().len()) {
+            transaction.insert_many(window.to_vec()).await?;
+        }
+
+        for window in network_tags.chunks(BATCH_SIZE / NetworkTag::columns().len()) {
+            transaction.insert_many(window.to_vec()).await?;
+        }
+
+        for window in tags.chunks(BATCH_SIZE / AddressTag::columns().len()) {
+            transaction.insert_many(window.to_vec()).await?;
+        }
+
+        for window in history.chunks(BATCH_SIZE / StatusTransition::columns().len()) {
+            transaction.insert_many(window.to_vec()).await?;
+        }
+
+        for window in alerts.chunks(BATCH_SIZE / CapacityAlert::columns().len()) {
+            transaction.insert_many(window.to_vec()).await?;
+        }
+
+        if let Some(father) = old.father {
+            let father = transaction
+                .get_for_update::<Network>(NetwCondition::p_key(father))
+                .await?;
+
+            let len = networks.len() - 1;
+
+            transaction
+                .update::<Network, _, _>(
+                    father.update_children(len.try_into().unwrap_or(i32::MAX)),
+                    NetwCondition::p_key(father.id),
+                )
+                .await?;
+
+            // The father counts the new networks and their reservations instead of the old ones
+            update_host_count(&mut transaction, father, |x| {
+                x.less_used_more_free(policy);
+                x.less_free_more_used(len as u128 + reserved);
+            })
+            .await?;
+        }
+
+        Ok(networks)
     }
+    .await;
 
-    Ok(QueryResult::new(10).into())
+    let networks = match resp {
+        Ok(e) => e,
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    transaction.commit().await?;
+
+    Ok(ResponseQuery::new(
+        Some(networks),
+        None,
+        None,
+        StatusCode::OK,
+    ))
 }
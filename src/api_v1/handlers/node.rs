@@ -1,13 +1,17 @@
 use super::{
     IsAdministrator, Json, PaginationParams, Path, Query, Repository, ResponseDefault, State,
-    StateType, Uuid, entries,
+    StateType, Uuid, custom_field, entries,
 };
 use crate::{
-    models::node::{Node, NodeCondition, UpdateNode},
+    models::{
+        custom_field::FieldTarget,
+        node::{Node, NodeCondition, UpdateNode},
+        tag::TagJoin,
+    },
     response::ResponseQuery,
 };
 use axum::http::StatusCode;
-use entries::models::NodeCreateEntry;
+use entries::{models::NodeCreateEntry, params::ParamTags};
 use serde_json::json;
 
 pub async fn create(
@@ -15,6 +19,8 @@ pub async fn create(
     _: IsAdministrator,
     Json(node): Json<NodeCreateEntry>,
 ) -> ResponseDefault<()> {
+    custom_field::validate(&state, FieldTarget::Node, &node.custom_fields).await?;
+
     Ok(state.insert::<Node>(node.into()).await?.into())
 }
 
@@ -24,6 +30,10 @@ pub async fn update(
     Path(id): Path<Uuid>,
     Json(new): Json<UpdateNode>,
 ) -> ResponseDefault<()> {
+    if let Some(custom_fields) = &new.custom_fields {
+        custom_field::validate(&state, FieldTarget::Node, custom_fields).await?;
+    }
+
     Ok(state
         .update::<Node, _>(new, NodeCondition::p_key(id))
         .await?
@@ -32,9 +42,12 @@ pub async fn update(
 
 pub async fn get(
     State(state): State<StateType>,
-    Query(params): Query<NodeCondition>,
+    Query(mut params): Query<NodeCondition>,
+    Query(tags): Query<ParamTags>,
     Query(PaginationParams { offset, limit }): Query<PaginationParams>,
 ) -> ResponseDefault<Vec<Node>> {
+    params.tags = tags.filter(TagJoin::NODE);
+
     let data = state.get::<Node>(params, limit, offset).await?;
 
     let metadata = Some(json!({
@@ -0,0 +1,189 @@
+use super::{
+    IsAdministrator, Json, Path, Repository, ResponseDefault, ResponseError, State, StateType,
+    StatusCode, Uuid, entries,
+};
+use crate::{
+    database::{repository::error::RepositoryError, transaction::Transaction as _},
+    models::network::{
+        Kind, NetwCondition, Network,
+        addresses::{AddrCondition, Addresses, StatusAddr},
+        scope::{Scope, ScopeCondition, ScopeUsage, UpdateScope},
+    },
+    response::ResponseQuery,
+};
+use entries::models::ScopeCreateEntry;
+use libipam::types::host_count::HostCount;
+use serde_json::json;
+
+pub async fn create(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(network_id): Path<Uuid>,
+    Json(scope): Json<ScopeCreateEntry>,
+) -> ResponseDefault<()> {
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        // The network is locked, so the scopes of the network are created one at a time
+        let network = transaction
+            .get_for_update::<Network>(NetwCondition::p_key(network_id))
+            .await?;
+
+        if network.kind != Kind::Pool {
+            return Err(ResponseError::builder()
+                .title("Network isn't a pool".to_string())
+                .detail(format!("The network {} isn't a pool", network.subnet))
+                .status(StatusCode::BAD_REQUEST)
+                .build());
+        }
+
+        let scope = scope.scope(network_id);
+        let range = network.addresses();
+
+        if range.index_of(scope.start_ip).is_none()
+            || range.index_of(scope.end_ip).is_none()
+            || range.index_of(scope.start_ip) > range.index_of(scope.end_ip)
+        {
+            return Err(ResponseError::builder()
+                .title("Invalid scope".to_string())
+                .detail(format!(
+                    "The scope from {} to {} isn't a range of hosts of {}",
+                    scope.start_ip, scope.end_ip, network.subnet
+                ))
+                .status(StatusCode::BAD_REQUEST)
+                .build());
+        }
+
+        if let Some(other) = transaction
+            .get::<Scope>(ScopeCondition::network(network_id), None, None)
+            .await?
+            .iter()
+            .find(|x| x.overlaps(&scope))
+        {
+            return Err(ResponseError::builder()
+                .title("Scope overlap".to_string())
+                .detail(format!(
+                    "The scope overlaps the scope from {} to {}",
+                    other.start_ip, other.end_ip
+                ))
+                .status(StatusCode::CONFLICT)
+                .build());
+        }
+
+        Ok(transaction.insert::<Scope>(scope).await?)
+    }
+    .await;
+
+    let resp = match resp {
+        Ok(e) => e,
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    transaction.commit().await?;
+
+    Ok(resp.into())
+}
+
+/// Scopes of the network with the addresses used inside each of them
+pub async fn get(
+    State(state): State<StateType>,
+    Path(network_id): Path<Uuid>,
+) -> ResponseDefault<Vec<ScopeUsage>> {
+    let scopes = scopes(&state, network_id).await?;
+
+    let used = match state
+        .get::<Addresses>(
+            AddrCondition {
+                network_id: Some(network_id),
+                ..Default::default()
+            },
+            None,
+            None,
+        )
+        .await
+    {
+        Ok(e) => e,
+        Err(RepositoryError::RowNotFound) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    }
+    .into_iter()
+    .filter(|x| x.status != StatusAddr::Unknown)
+    .map(|x| x.ip.addr())
+    .collect::<Vec<_>>();
+
+    let data = scopes
+        .into_iter()
+        .map(|scope| {
+            let n = used.iter().filter(|x| scope.contains(**x)).count() as u128;
+
+            ScopeUsage {
+                used: HostCount::from(n),
+                free: HostCount::from(scope.len().saturating_sub(n)),
+                scope,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+pub async fn update(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path((network_id, id)): Path<(Uuid, Uuid)>,
+    Json(updater): Json<UpdateScope>,
+) -> ResponseDefault<()> {
+    Ok(state
+        .update::<Scope, _>(
+            updater,
+            ScopeCondition {
+                id: Some(id),
+                network_id: Some(network_id),
+                ..Default::default()
+            },
+        )
+        .await?
+        .into())
+}
+
+/// The addresses of the scope aren't deleted
+pub async fn delete(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path((network_id, id)): Path<(Uuid, Uuid)>,
+) -> ResponseDefault<()> {
+    Ok(state
+        .delete::<Scope>(ScopeCondition {
+            id: Some(id),
+            network_id: Some(network_id),
+            ..Default::default()
+        })
+        .await?
+        .into())
+}
+
+/// Scopes of a network, empty if it doesn't have any
+pub async fn scopes(state: &StateType, network_id: Uuid) -> Result<Vec<Scope>, ResponseError> {
+    match state
+        .get::<Scope>(ScopeCondition::network(network_id), None, None)
+        .await
+    {
+        Ok(e) => Ok(e),
+        Err(RepositoryError::RowNotFound) => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,161 @@
+use super::{
+    IsAdministrator, Json, PaginationParams, Path, Query, Repository, ResponseDefault,
+    ResponseError, State, StateType, StatusCode, Uuid, addresses::policy_reserved, entries,
+    extractors::CurrentUser,
+};
+use crate::{
+    database::transaction::{BuilderPgTransaction, Transaction as _},
+    models::network::{
+        NetwCondition, Network, StatusNetwork,
+        status::{StatusTransition, StatusTransitionCondition, UpdateStatus},
+    },
+    response::ResponseQuery,
+};
+use entries::models::{StatusBulkEntry, StatusEntry};
+use serde_json::json;
+
+pub async fn transition(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<Uuid>,
+    Json(StatusEntry { status, reason }): Json<StatusEntry>,
+) -> ResponseDefault<Vec<StatusTransition>> {
+    transition_many(&state, vec![id], status, reason, user).await
+}
+
+/// Changes the status of several networks in one transaction, the networks are locked in order
+pub async fn bulk(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    CurrentUser(user): CurrentUser,
+    Json(StatusBulkEntry {
+        mut networks,
+        status,
+        reason,
+    }): Json<StatusBulkEntry>,
+) -> ResponseDefault<Vec<StatusTransition>> {
+    networks.sort_unstable();
+    networks.dedup();
+
+    if networks.is_empty() {
+        return Err(ResponseError::builder()
+            .title("Without networks".to_string())
+            .detail("The transition needs at least one network".to_string())
+            .status(StatusCode::BAD_REQUEST)
+            .build());
+    }
+
+    transition_many(&state, networks, status, reason, user).await
+}
+
+/// Transitions recorded for the networks, from the newest
+pub async fn history(
+    State(state): State<StateType>,
+    Query(params): Query<StatusTransitionCondition>,
+    Query(PaginationParams { offset, limit }): Query<PaginationParams>,
+) -> ResponseDefault<Vec<StatusTransition>> {
+    let data = state.get::<StatusTransition>(params, limit, offset).await?;
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+async fn transition_many(
+    state: &StateType,
+    networks: Vec<Uuid>,
+    status: StatusNetwork,
+    reason: Option<String>,
+    user: Uuid,
+) -> ResponseDefault<Vec<StatusTransition>> {
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        let mut data = Vec::with_capacity(networks.len());
+
+        for id in networks {
+            let network = transaction
+                .get_for_update::<Network>(NetwCondition::p_key(id))
+                .await?;
+
+            data.push(apply(&mut transaction, network, status, reason.clone(), user).await?);
+        }
+
+        Ok(data)
+    }
+    .await;
+
+    let data = match resp {
+        Ok(e) => e,
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    transaction.commit().await?;
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// Checks the transition of the network with its lifecycle, and records it
+async fn apply(
+    transaction: &mut BuilderPgTransaction<'_>,
+    network: Network,
+    status: StatusNetwork,
+    reason: Option<String>,
+    user: Uuid,
+) -> Result<StatusTransition, ResponseError> {
+    // The used hosts count the subnets and the used addresses of the network, the addresses
+    // reserved by the policy don't put it in use
+    let reserved = policy_reserved(transaction, network.id).await?.len() as u128;
+    let in_use = network.used != reserved;
+
+    if !network.status.can_transition(status, in_use) {
+        // A move back is only refused while the network is in use
+        let detail = if network.status == status {
+            format!("The network {} is already {status:?}", network.subnet)
+        } else {
+            format!(
+                "The network {} cannot go from {:?} to {status:?} while it has subnets or used addresses",
+                network.subnet, network.status
+            )
+        };
+
+        return Err(ResponseError::builder()
+            .title("Invalid status transition".to_string())
+            .detail(detail)
+            .status(StatusCode::CONFLICT)
+            .build());
+    }
+
+    transaction
+        .update::<Network, _, _>(UpdateStatus { status }, NetwCondition::p_key(network.id))
+        .await?;
+
+    let record = StatusTransition::new(network.id, network.status, status, reason, user);
+    transaction.insert(record.clone()).await?;
+
+    Ok(record)
+}
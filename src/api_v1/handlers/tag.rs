@@ -0,0 +1,242 @@
+use super::{
+    IsAdministrator, Json, PaginationParams, Path, Query, Repository, ResponseDefault,
+    ResponseError, State, StateType, StatusCode, Uuid, entries,
+};
+use crate::{
+    database::repository::{MapQuery, QueryResult, Table, error::RepositoryError},
+    models::{
+        network::{
+            NetwCondition, Network,
+            addresses::{AddrCondition, Addresses},
+        },
+        node::{Node, NodeCondition},
+        tag::{
+            AddressTag, AddressTagCondition, NetworkTag, NetworkTagCondition, NodeTag,
+            NodeTagCondition, Tag, TagCondition, UpdateTag, VlanTag, VlanTagCondition,
+        },
+        vlan::{Vlan, VlanCondition},
+    },
+    response::ResponseQuery,
+};
+use entries::models::{TagCreateEntry, TagObject};
+use serde_json::json;
+use sqlx::postgres::PgRow;
+
+pub async fn create(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Json(tag): Json<TagCreateEntry>,
+) -> ResponseDefault<()> {
+    check_color(tag.color.as_deref())?;
+
+    Ok(state.insert::<Tag>(tag.into()).await?.into())
+}
+
+pub async fn get(
+    State(state): State<StateType>,
+    Query(params): Query<TagCondition>,
+    Query(PaginationParams { offset, limit }): Query<PaginationParams>,
+) -> ResponseDefault<Vec<Tag>> {
+    let data = state.get::<Tag>(params, limit, offset).await?;
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+pub async fn update(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+    Json(updater): Json<UpdateTag>,
+) -> ResponseDefault<()> {
+    check_color(updater.color.as_deref())?;
+
+    Ok(state
+        .update::<Tag, _>(updater, TagCondition::p_key(id))
+        .await?
+        .into())
+}
+
+/// The tag is detached from every object by cascade
+pub async fn delete(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+) -> ResponseDefault<()> {
+    Ok(state.delete::<Tag>(TagCondition::p_key(id)).await?.into())
+}
+
+pub async fn attach(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+    Json(object): Json<TagObject>,
+) -> ResponseDefault<()> {
+    state.get_one::<Tag>(TagCondition::p_key(id)).await?;
+
+    let resp = match object {
+        TagObject::Network(network_id) => {
+            state
+                .get_one::<Network>(NetwCondition::p_key(network_id))
+                .await?;
+
+            insert_once(
+                &state,
+                NetworkTag {
+                    network_id,
+                    tag_id: id,
+                },
+                NetworkTagCondition {
+                    network_id: Some(network_id),
+                    tag_id: Some(id),
+                },
+            )
+            .await?
+        }
+        TagObject::Address { ip, network_id } => {
+            state
+                .get_one::<Addresses>(AddrCondition::p_key(ip, network_id))
+                .await?;
+
+            insert_once(
+                &state,
+                AddressTag {
+                    ip,
+                    network_id,
+                    tag_id: id,
+                },
+                AddressTagCondition {
+                    ip: Some(ip),
+                    network_id: Some(network_id),
+                    tag_id: Some(id),
+                },
+            )
+            .await?
+        }
+        TagObject::Node(node_id) => {
+            state.get_one::<Node>(NodeCondition::p_key(node_id)).await?;
+
+            insert_once(
+                &state,
+                NodeTag {
+                    node_id,
+                    tag_id: id,
+                },
+                NodeTagCondition {
+                    node_id: Some(node_id),
+                    tag_id: Some(id),
+                },
+            )
+            .await?
+        }
+        TagObject::Vlan(vlan_id) => {
+            state.get_one::<Vlan>(VlanCondition::p_key(vlan_id)).await?;
+
+            insert_once(
+                &state,
+                VlanTag {
+                    vlan_id,
+                    tag_id: id,
+                },
+                VlanTagCondition {
+                    vlan_id: Some(vlan_id),
+                    tag_id: Some(id),
+                },
+            )
+            .await?
+        }
+    };
+
+    Ok(resp.into())
+}
+
+pub async fn detach(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+    Json(object): Json<TagObject>,
+) -> ResponseDefault<()> {
+    let resp = match object {
+        TagObject::Network(network_id) => {
+            state
+                .delete::<NetworkTag>(NetworkTagCondition {
+                    network_id: Some(network_id),
+                    tag_id: Some(id),
+                })
+                .await?
+        }
+        TagObject::Address { ip, network_id } => {
+            state
+                .delete::<AddressTag>(AddressTagCondition {
+                    ip: Some(ip),
+                    network_id: Some(network_id),
+                    tag_id: Some(id),
+                })
+                .await?
+        }
+        TagObject::Node(node_id) => {
+            state
+                .delete::<NodeTag>(NodeTagCondition {
+                    node_id: Some(node_id),
+                    tag_id: Some(id),
+                })
+                .await?
+        }
+        TagObject::Vlan(vlan_id) => {
+            state
+                .delete::<VlanTag>(VlanTagCondition {
+                    vlan_id: Some(vlan_id),
+                    tag_id: Some(id),
+                })
+                .await?
+        }
+    };
+
+    Ok(resp.into())
+}
+
+/// Inserts the row of the join table if the tag isn't attached yet
+async fn insert_once<T>(
+    state: &StateType,
+    row: T,
+    condition: impl MapQuery,
+) -> Result<QueryResult, ResponseError>
+where
+    T: Table + From<PgRow>,
+{
+    match state.get_one::<T>(condition).await {
+        Ok(_) => Err(ResponseError::builder()
+            .title("Tag already attached".to_string())
+            .detail("The tag is already attached to the object".to_string())
+            .status(StatusCode::CONFLICT)
+            .build()),
+        Err(RepositoryError::RowNotFound) => Ok(state.insert(row).await?),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn check_color(color: Option<&str>) -> Result<(), ResponseError> {
+    let valid = color.is_none_or(|x| {
+        x.len() == 7 && x.starts_with('#') && x.chars().skip(1).all(|x| x.is_ascii_hexdigit())
+    });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ResponseError::builder()
+            .title("Invalid colour".to_string())
+            .detail("The colour must be as #rrggbb".to_string())
+            .status(StatusCode::BAD_REQUEST)
+            .build())
+    }
+}
@@ -1,7 +1,13 @@
-use super::{PaginationParams, ResponseDefault, State, StateType};
+use super::{
+    PaginationParams, ResponseDefault, State, StateType, custom_field, entries::params::ParamTags,
+};
 use crate::{
     database::repository::Repository,
-    models::vlan::{UpdateVlan, Vlan, VlanCondition},
+    models::{
+        custom_field::FieldTarget,
+        tag::TagJoin,
+        vlan::{UpdateVlan, Vlan, VlanCondition},
+    },
     response::ResponseQuery,
 };
 use axum::{
@@ -13,9 +19,35 @@ use libipam::types::vlan::VlanId;
 use serde_json::json;
 
 pub async fn insert(State(state): State<StateType>, Json(vlan): Json<Vlan>) -> ResponseDefault<()> {
+    custom_field::validate(&state, FieldTarget::Vlan, &vlan.custom_fields).await?;
+
     Ok(state.insert(vlan).await?.into())
 }
 
+pub async fn list(
+    State(state): State<StateType>,
+    Query(mut params): Query<VlanCondition>,
+    Query(tags): Query<ParamTags>,
+    Query(PaginationParams { offset, limit }): Query<PaginationParams>,
+) -> ResponseDefault<Vec<Vlan>> {
+    params.tags = tags.filter(TagJoin::VLAN);
+
+    let resp = state.get::<Vlan>(params, limit, offset).await?;
+
+    let metadata = Some(json!({
+        "length": resp.len(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(resp),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
 pub async fn get(
     State(state): State<StateType>,
     Path(id): Path<VlanId>,
@@ -44,6 +76,10 @@ pub async fn update(
     Path(id): Path<VlanId>,
     Json(vlan): Json<UpdateVlan>,
 ) -> ResponseDefault<()> {
+    if let Some(custom_fields) = &vlan.custom_fields {
+        custom_field::validate(&state, FieldTarget::Vlan, custom_fields).await?;
+    }
+
     Ok(state
         .update::<Vlan, _>(vlan, VlanCondition::p_key(id))
         .await?
@@ -0,0 +1,89 @@
+use super::{
+    IsAdministrator, Json, PaginationParams, Path, Query, Repository, ResponseDefault,
+    ResponseError, State, StateType, Uuid, entries,
+};
+use crate::{
+    database::repository::{Nullable, error::RepositoryError},
+    models::{
+        network::{NetwCondition, Network},
+        vrf::{UpdateVrf, Vrf, VrfCondition},
+    },
+    response::ResponseQuery,
+};
+use axum::http::StatusCode;
+use entries::models::VrfCreateEntry;
+use serde_json::json;
+
+pub async fn create(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Json(vrf): Json<VrfCreateEntry>,
+) -> ResponseDefault<()> {
+    Ok(state.insert::<Vrf>(vrf.into()).await?.into())
+}
+
+pub async fn update(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+    Json(new): Json<UpdateVrf>,
+) -> ResponseDefault<()> {
+    Ok(state
+        .update::<Vrf, _>(new, VrfCondition::p_key(id))
+        .await?
+        .into())
+}
+
+pub async fn get(
+    State(state): State<StateType>,
+    Query(params): Query<VrfCondition>,
+    Query(PaginationParams { offset, limit }): Query<PaginationParams>,
+) -> ResponseDefault<Vec<Vrf>> {
+    let data = state.get::<Vrf>(params, limit, offset).await?;
+
+    let metadata = Some(json!({
+        "length": data.len(),
+        "success": true,
+        "status": StatusCode::OK.as_u16(),
+    }));
+
+    Ok(ResponseQuery::new(
+        Some(data),
+        metadata,
+        None,
+        StatusCode::OK,
+    ))
+}
+
+/// The vrfs with networks cannot be deleted
+pub async fn delete(
+    State(state): State<StateType>,
+    _: IsAdministrator,
+    Path(id): Path<Uuid>,
+) -> ResponseDefault<()> {
+    let networks = match state
+        .get::<Network>(
+            NetwCondition {
+                vrf: Some(Nullable(Some(id))),
+                ..Default::default()
+            },
+            Some(1),
+            None,
+        )
+        .await
+    {
+        Ok(e) => e,
+        Err(RepositoryError::RowNotFound) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    if !networks.is_empty() {
+        return Err(ResponseError::builder()
+            .title("Vrf not empty".to_string())
+            .detail(format!("The vrf {id} has networks"))
+            .status(StatusCode::CONFLICT)
+            .build());
+    }
+
+    Ok(state.delete::<Vrf>(VrfCondition::p_key(id)).await?.into())
+}
@@ -6,21 +6,45 @@ use axum::{
     routing::{delete, get, patch, post},
 };
 
-use self::handlers::{addresses, auth, network, node, vlan};
+use self::handlers::{
+    addresses, auth, calculator, capacity, custom_field, network, node, scope, status, tag, vlan,
+    vrf,
+};
 
 pub fn api_v1() -> Router<StateType> {
     let network = Router::new()
         .route("/subnet/{father}", post(network::subnetting))
         .route("/", post(network::create).get(network::get))
-        .route("/{id}", delete(network::delete).patch(network::update));
-
-    let addrs = Router::new().route("/", post(addresses::insert)).route(
-        "/{network_id}",
-        get(addresses::get)
-            .delete(addresses::delete)
-            .patch(addresses::update)
-            .post(addresses::create_all_ip_addresses),
-    );
+        .route("/tree", get(network::tree))
+        .route("/lookup", get(network::lookup))
+        .route("/capacity", get(capacity::over_threshold))
+        .route("/alerts", get(capacity::alerts))
+        .route("/recount", post(network::recount))
+        .route("/status", post(status::bulk).get(status::history))
+        .route("/{id}", delete(network::delete).patch(network::update))
+        .route("/{id}/allocate", post(network::allocate))
+        .route("/{id}/free", get(network::free))
+        .route("/{id}/merge", post(network::merge))
+        .route("/{id}/resize", post(network::resize))
+        .route("/{id}/split", post(network::split))
+        .route("/{id}/status", post(status::transition))
+        .route("/{id}/vlsm", post(network::vlsm))
+        .route("/{id}/scopes", post(scope::create).get(scope::get))
+        .route(
+            "/{id}/scopes/{scope}",
+            patch(scope::update).delete(scope::delete),
+        );
+
+    let addrs = Router::new()
+        .route("/", post(addresses::insert))
+        .route(
+            "/{network_id}",
+            get(addresses::get)
+                .delete(addresses::delete)
+                .patch(addresses::update)
+                .post(addresses::create_all_ip_addresses),
+        )
+        .route("/{network_id}/allocate", post(addresses::allocate));
 
     let node = Router::new().route(
         "/",
@@ -34,15 +58,43 @@ pub fn api_v1() -> Router<StateType> {
         .route("/", post(auth::create))
         .route("/{id}", patch(auth::update).delete(auth::delete));
 
-    let vlan = Router::new().route("/", post(vlan::insert)).route(
-        "/{id}",
-        get(vlan::get).delete(vlan::delete).patch(vlan::update),
-    );
+    let vlan = Router::new()
+        .route("/", post(vlan::insert).get(vlan::list))
+        .route(
+            "/{id}",
+            get(vlan::get).delete(vlan::delete).patch(vlan::update),
+        );
+
+    let vrf = Router::new()
+        .route("/", post(vrf::create).get(vrf::get))
+        .route("/{id}", patch(vrf::update).delete(vrf::delete));
+
+    let custom_field = Router::new()
+        .route("/", post(custom_field::create).get(custom_field::get))
+        .route(
+            "/{id}",
+            patch(custom_field::update).delete(custom_field::delete),
+        );
+
+    let tag = Router::new()
+        .route("/", post(tag::create).get(tag::get))
+        .route("/{id}", patch(tag::update).delete(tag::delete))
+        .route("/{id}/attach", post(tag::attach))
+        .route("/{id}/detach", post(tag::detach));
+
+    let calculator = Router::new()
+        .route("/", get(calculator::info))
+        .route("/range", get(calculator::range))
+        .route("/summarize", post(calculator::summarize));
 
     Router::new()
         .nest("/networks", network)
+        .nest("/calculator", calculator)
         .nest("/nodes", node)
         .nest("/users", user)
         .nest("/vlans", vlan)
+        .nest("/vrfs", vrf)
+        .nest("/custom-fields", custom_field)
+        .nest("/tags", tag)
         .nest("/addrs", addrs)
 }
@@ -1,4 +1,5 @@
-use crate::database::RepositoryInjection;
+use crate::{database::RepositoryInjection, models::network::addresses::AddrMode};
+use libipam::services::{capacity::Thresholds, reservation::ReservePolicy};
 use sqlx::Postgres;
 use std::sync::Arc;
 use tokio::sync::Semaphore;
@@ -9,11 +10,26 @@ pub type StateType = Arc<AppState>;
 pub struct AppState {
     pub db: RepositoryInjection<Postgres>,
     pub heavy_task: Semaphore,
+    pub addresses_mode: AddrMode,
+    pub thresholds: Thresholds,
+    pub reserve_policy: ReservePolicy,
 }
 
 impl AppState {
-    pub fn new(db: RepositoryInjection<Postgres>, heavy_task: Semaphore) -> Self {
-        Self { db, heavy_task }
+    pub fn new(
+        db: RepositoryInjection<Postgres>,
+        heavy_task: Semaphore,
+        addresses_mode: AddrMode,
+        thresholds: Thresholds,
+        reserve_policy: ReservePolicy,
+    ) -> Self {
+        Self {
+            db,
+            heavy_task,
+            addresses_mode,
+            thresholds,
+            reserve_policy,
+        }
     }
 }
 
@@ -21,6 +37,18 @@ impl AppState {
     pub fn heavy_task(&self) -> &Semaphore {
         &self.heavy_task
     }
+
+    pub fn addresses_mode(&self) -> AddrMode {
+        self.addresses_mode
+    }
+
+    pub fn thresholds(&self) -> Thresholds {
+        self.thresholds
+    }
+
+    pub fn reserve_policy(&self) -> ReservePolicy {
+        self.reserve_policy
+    }
 }
 
 impl std::ops::Deref for AppState {
@@ -1,6 +1,8 @@
-use std::{env::var, net::IpAddr};
+use std::{env::var, net::IpAddr, time::Duration};
 
+use crate::models::network::addresses::AddrMode;
 use axum::http::HeaderValue;
+use libipam::services::{capacity::Thresholds, reservation::ReservePolicy};
 #[derive(Debug)]
 pub struct Config {
     pub database: Database,
@@ -11,6 +13,8 @@ impl Config {
     pub fn init() -> Self {
         dotenv::dotenv().ok();
 
+        let thresholds = Thresholds::default();
+
         Self {
             database: Database {
                 name: var("DATABASE_NAME").expect("Database name not define"),
@@ -41,6 +45,61 @@ impl Config {
                             .filter_map(|x| x.parse::<HeaderValue>().ok())
                             .collect()
                     }),
+                addresses_mode: var("ADDRESSES_MODE")
+                    .ok()
+                    .filter(|x| !x.is_empty())
+                    .map_or(AddrMode::default(), |x| {
+                        x.parse().expect("Invalid addresses mode")
+                    }),
+                thresholds: Thresholds::new(
+                    var("CAPACITY_WARNING")
+                        .ok()
+                        .filter(|x| !x.is_empty())
+                        .map_or(thresholds.warning(), |x| {
+                            x.parse().expect("Invalid warning threshold")
+                        }),
+                    var("CAPACITY_CRITICAL")
+                        .ok()
+                        .filter(|x| !x.is_empty())
+                        .map_or(thresholds.critical(), |x| {
+                            x.parse().expect("Invalid critical threshold")
+                        }),
+                )
+                .expect("Invalid capacity thresholds"),
+                capacity_interval: var("CAPACITY_INTERVAL")
+                    .ok()
+                    .filter(|x| !x.is_empty())
+                    .map_or(Duration::from_secs(300), |x| {
+                        x.parse()
+                            .ok()
+                            .filter(|x| *x != 0)
+                            .map(Duration::from_secs)
+                            .expect("Invalid capacity interval, it must be at least 1 second")
+                    }),
+                recount_interval: var("RECOUNT_INTERVAL")
+                    .ok()
+                    .filter(|x| !x.is_empty())
+                    .map_or(Duration::from_secs(3600), |x| {
+                        x.parse()
+                            .ok()
+                            .filter(|x| *x != 0)
+                            .map(Duration::from_secs)
+                            .expect("Invalid recount interval, it must be at least 1 second")
+                    }),
+                recount_fix: var("RECOUNT_FIX")
+                    .ok()
+                    .filter(|x| !x.is_empty())
+                    .is_some_and(|x| x.parse().expect("Invalid recount fix")),
+                reserve_policy: ReservePolicy::new(
+                    var("RESERVE_FIRST")
+                        .ok()
+                        .filter(|x| !x.is_empty())
+                        .map_or(0, |x| x.parse().expect("Invalid reserved first hosts")),
+                    var("RESERVE_LAST")
+                        .ok()
+                        .filter(|x| !x.is_empty())
+                        .map_or(0, |x| x.parse().expect("Invalid reserved last hosts")),
+                ),
             },
         }
     }
@@ -60,4 +119,18 @@ pub struct Backend {
     pub port: u16,
     pub ip: IpAddr,
     pub allow_origin: Option<Vec<HeaderValue>>,
+    pub addresses_mode: AddrMode,
+
+    /// Global thresholds of the networks
+    pub thresholds: Thresholds,
+
+    /// Period of the evaluation of the thresholds
+    pub capacity_interval: Duration,
+
+    /// Period of the check of the host counters, the drift is fixed with `recount_fix`
+    pub recount_interval: Duration,
+    pub recount_fix: bool,
+
+    /// Global reservation policy of the networks
+    pub reserve_policy: ReservePolicy,
 }
@@ -8,7 +8,7 @@ use repository::{
 };
 use sql::SqlOperations;
 use sqlx::{
-    Database, Pool, Postgres,
+    Database, Pool, Postgres, Row,
     postgres::{PgPool, PgPoolOptions, PgRow},
 };
 use std::{collections::HashMap, fmt::Debug};
@@ -43,26 +43,12 @@ impl Repository for RepositoryInjection<Postgres> {
         Ok(res.into())
     }
 
-    async fn insert_many<T: Table>(&self, data: Vec<T>) -> ResultRepository<QueryResult> {
-        tracing::trace!("REPOSITORY");
-        tracing::trace!("1 input (data) - {:?}", data);
-
-        let len = data.len();
-
-        tracing::trace!("1 input (data length) - {:?}", len);
-
-        Ok(SqlOperations::insert_many(data, &T::query_insert(len))
-            .execute(&self.0)
-            .await?
-            .into())
-    }
-
     async fn get_one<T: Table + From<PgRow>>(
         &self,
         primary_key: impl MapQuery,
     ) -> ResultRepository<T> {
         let mut query = T::query_select();
-        let query = SqlOperations::get(&mut query, primary_key, None, None);
+        let query = SqlOperations::get(&mut query, primary_key, None, None, None);
 
         Ok(query.fetch_one(&self.0).await?.into())
     }
@@ -79,7 +65,7 @@ impl Repository for RepositoryInjection<Postgres> {
         tracing::trace!("3 input (offset) - {:?}", offset);
 
         let mut query = T::query_select();
-        let query = SqlOperations::get(&mut query, column_data, limit, offset)
+        let query = SqlOperations::get(&mut query, column_data, T::order_by(), limit, offset)
             .fetch_all(&self.0)
             .await?
             .into_iter()
@@ -93,6 +79,19 @@ impl Repository for RepositoryInjection<Postgres> {
         }
     }
 
+    async fn count<T: Table>(&self, condition: impl MapQuery) -> ResultRepository<u64> {
+        tracing::trace!("REPOSITORY");
+        tracing::trace!("1 input (condition) - {:?}", condition);
+
+        let mut query = T::query_count();
+        let count = SqlOperations::get(&mut query, condition, None, None, None)
+            .fetch_one(&self.0)
+            .await?
+            .try_get::<i64, _>(0)?;
+
+        Ok(count.unsigned_abs())
+    }
+
     async fn update<T: Table, U: Updatable>(
         &self,
         updater: U,
@@ -1,11 +1,16 @@
 use super::PgRow;
 use crate::models::{
-    network::{self, Kind, StatusNetwork, addresses::StatusAddr},
+    custom_field::{CustomFields, CustomFieldsFilter, FieldKind, FieldTarget},
+    network::{self, Kind, StatusNetwork, addresses::StatusAddr, scope::ScopePurpose},
+    tag::TagFilter,
     user::Role,
 };
 use error::RepositoryError;
 use ipnet::IpNet;
-use libipam::types::{host_count::HostCount, vlan::VlanId};
+use libipam::{
+    services::{allocation::Strategy, capacity::Level},
+    types::{host_count::HostCount, vlan::VlanId},
+};
 use serde::Serialize;
 use std::{collections::HashMap, fmt::Debug, net::IpAddr};
 use uuid::Uuid;
@@ -25,12 +30,13 @@ pub trait Repository {
         primary_key: impl MapQuery,
     ) -> impl Future<Output = ResultRepository<T>>;
 
-    fn insert<T: Table>(&self, data: T) -> impl Future<Output = ResultRepository<QueryResult>>;
-
-    fn insert_many<T: Table>(
+    /// Number of rows that match the condition
+    fn count<T: Table>(
         &self,
-        data: Vec<T>,
-    ) -> impl Future<Output = ResultRepository<QueryResult>>;
+        condition: impl MapQuery,
+    ) -> impl Future<Output = ResultRepository<u64>>;
+
+    fn insert<T: Table>(&self, data: T) -> impl Future<Output = ResultRepository<QueryResult>>;
 
     fn update<T: Table, U: Updatable>(
         &self,
@@ -101,6 +107,9 @@ pub trait Table: Send + Sync + Debug {
 
     fn columns() -> Vec<&'static str>;
 
+    /// Columns of the `ORDER BY` clause of the select queries
+    fn order_by() -> Option<&'static str>;
+
     fn query_update() -> String
     where
         Self: Table,
@@ -118,6 +127,10 @@ pub trait Table: Send + Sync + Debug {
     fn query_select() -> String {
         format!("SELECT * FROM {}", Self::name())
     }
+
+    fn query_count() -> String {
+        format!("SELECT COUNT(*) FROM {}", Self::name())
+    }
 }
 
 pub trait Updatable: Sync + Send + Debug {
@@ -131,6 +144,9 @@ pub mod error {
         RowNotFound,
         ColumnNotFound(String),
         UpdaterEmpty,
+
+        /// The row breaks an exclusion constraint with other rows, as an overlap
+        Conflict(String),
     }
 
     impl std::fmt::Display for RepositoryError {
@@ -140,6 +156,7 @@ pub mod error {
                 Self::RowNotFound => write!(f, "Row not found"),
                 Self::ColumnNotFound(e) => write!(f, "The column {e} not found"),
                 Self::UpdaterEmpty => write!(f, "There are not values to change"),
+                Self::Conflict(e) => write!(f, "{e}"),
             }
         }
     }
@@ -151,6 +168,10 @@ pub mod error {
             match value {
                 sqlx::Error::ColumnNotFound(e) => Self::ColumnNotFound(e),
                 sqlx::Error::RowNotFound => Self::RowNotFound,
+                // exclusion_violation
+                sqlx::Error::Database(e) if e.code().is_some_and(|x| x == "23P01") => {
+                    Self::Conflict(e.message().to_string())
+                }
                 e => Self::Sqlx(e.to_string()),
             }
         }
@@ -174,9 +195,69 @@ pub enum TypeTable {
     Time(time::OffsetDateTime),
     Bool(bool),
     Kind(Kind),
+    IpNet(IpNet),
+    IpAddr(IpAddr),
+    IpOperation(IpOperation),
+    OptionVecString(Option<Vec<String>>),
+    FieldKind(FieldKind),
+    FieldTarget(FieldTarget),
+    CustomFields(CustomFields),
+    CustomFieldsFilter(CustomFieldsFilter),
+
+    /// Removes a key of the custom fields in an update
+    RemoveCustomField(String),
+    TagFilter(TagFilter),
+    OptionI16(Option<i16>),
+    F64(f64),
+    Level(Level),
+    ScopePurpose(ScopePurpose),
+    Strategy(Strategy),
     Null,
 }
 
+/// Value of a nullable column in a condition, `None` is compared with `IS NULL`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Nullable<T>(pub Option<T>);
+
+impl<T: Into<TypeTable>> From<Nullable<T>> for TypeTable {
+    fn from(value: Nullable<T>) -> Self {
+        value.0.map_or(Self::Null, Into::into)
+    }
+}
+
+/// Comparison of an inet or cidr column with a network, they use the GiST indexes
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IpOperation {
+    /// The column contains or is contained by the network
+    Overlaps(IpNet),
+
+    /// The column contains or is equal to the network
+    Contains(IpNet),
+
+    /// The column is contained by or is equal to the network
+    ContainedBy(IpNet),
+
+    /// The column is after or equal to the network, in the order of the addresses
+    From(IpNet),
+}
+
+impl IpOperation {
+    pub fn operator(&self) -> &'static str {
+        match self {
+            Self::Overlaps(_) => "&&",
+            Self::Contains(_) => ">>=",
+            Self::ContainedBy(_) => "<<=",
+            Self::From(_) => ">=",
+        }
+    }
+
+    pub fn network(&self) -> IpNet {
+        match self {
+            Self::Overlaps(e) | Self::Contains(e) | Self::ContainedBy(e) | Self::From(e) => *e,
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! bind_query {
     ($query:expr_2021, $value:expr_2021) => {
@@ -196,11 +277,104 @@ macro_rules! bind_query {
             TypeTable::HostCount(e) => $query.bind(e),
             TypeTable::I32(e) => $query.bind(e),
             TypeTable::StatusNetwork(e) => $query.bind(e),
+            TypeTable::IpNet(e) => $query.bind(e),
+            TypeTable::IpAddr(e) => $query.bind(e),
+            TypeTable::IpOperation(e) => $query.bind(e.network()),
+            TypeTable::OptionVecString(e) => $query.bind(e),
+            TypeTable::FieldKind(e) => $query.bind(e),
+            TypeTable::FieldTarget(e) => $query.bind(e),
+            TypeTable::CustomFields(e) => $query.bind(e),
+            TypeTable::CustomFieldsFilter(e) => $query.bind(sqlx::types::Json(e.0)),
+            TypeTable::RemoveCustomField(e) => $query.bind(e),
+            TypeTable::TagFilter(e) => $query.bind(e.names),
+            TypeTable::OptionI16(e) => $query.bind(e),
+            TypeTable::F64(e) => $query.bind(e),
+            TypeTable::Level(e) => $query.bind(e),
+            TypeTable::ScopePurpose(e) => $query.bind(e),
+            TypeTable::Strategy(e) => $query.bind(e),
             TypeTable::Null => $query,
         }
     };
 }
 
+impl From<Vec<String>> for TypeTable {
+    fn from(value: Vec<String>) -> Self {
+        Self::OptionVecString(Some(value))
+    }
+}
+
+impl From<Option<Vec<String>>> for TypeTable {
+    fn from(value: Option<Vec<String>>) -> Self {
+        Self::OptionVecString(value)
+    }
+}
+
+impl From<FieldKind> for TypeTable {
+    fn from(value: FieldKind) -> Self {
+        Self::FieldKind(value)
+    }
+}
+
+impl From<FieldTarget> for TypeTable {
+    fn from(value: FieldTarget) -> Self {
+        Self::FieldTarget(value)
+    }
+}
+
+impl From<CustomFields> for TypeTable {
+    fn from(value: CustomFields) -> Self {
+        Self::CustomFields(value)
+    }
+}
+
+impl From<CustomFieldsFilter> for TypeTable {
+    fn from(value: CustomFieldsFilter) -> Self {
+        Self::CustomFieldsFilter(value)
+    }
+}
+
+impl From<TagFilter> for TypeTable {
+    fn from(value: TagFilter) -> Self {
+        Self::TagFilter(value)
+    }
+}
+
+impl From<i16> for TypeTable {
+    fn from(value: i16) -> Self {
+        Self::OptionI16(Some(value))
+    }
+}
+
+impl From<Option<i16>> for TypeTable {
+    fn from(value: Option<i16>) -> Self {
+        Self::OptionI16(value)
+    }
+}
+
+impl From<f64> for TypeTable {
+    fn from(value: f64) -> Self {
+        Self::F64(value)
+    }
+}
+
+impl From<Level> for TypeTable {
+    fn from(value: Level) -> Self {
+        Self::Level(value)
+    }
+}
+
+impl From<ScopePurpose> for TypeTable {
+    fn from(value: ScopePurpose) -> Self {
+        Self::ScopePurpose(value)
+    }
+}
+
+impl From<Strategy> for TypeTable {
+    fn from(value: Strategy) -> Self {
+        Self::Strategy(value)
+    }
+}
+
 impl From<StatusNetwork> for TypeTable {
     fn from(value: StatusNetwork) -> Self {
         Self::StatusNetwork(value)
@@ -275,13 +449,19 @@ impl From<Option<Uuid>> for TypeTable {
 
 impl From<IpAddr> for TypeTable {
     fn from(value: IpAddr) -> Self {
-        Self::String(value.to_string())
+        Self::IpAddr(value)
     }
 }
 
 impl From<IpNet> for TypeTable {
     fn from(value: IpNet) -> Self {
-        Self::String(value.to_string())
+        Self::IpNet(value)
+    }
+}
+
+impl From<IpOperation> for TypeTable {
+    fn from(value: IpOperation) -> Self {
+        Self::IpOperation(value)
     }
 }
 
@@ -7,37 +7,70 @@ pub struct SqlOperations;
 // We've taken ownership of the query string and used a builder to add the behavior of limit, offset, and group by.
 
 impl SqlOperations {
-    pub fn get(
-        query: &mut String,
+    pub fn get<'a>(
+        query: &'a mut String,
         condition: impl MapQuery,
+        order_by: Option<&str>,
         limit: Option<i32>,
         offset: Option<i32>,
-    ) -> Query<'_, Postgres, PgArguments> {
+    ) -> Query<'a, Postgres, PgArguments> {
         tracing::trace!("SQL OPERATIONS");
         tracing::trace!("1 input (query) - {}", query);
         tracing::trace!("2 input (condition) - {:?}", condition);
         tracing::trace!("3 input (limit) - {:?}", limit);
         tracing::trace!("4 input (offset) - {:?}", offset);
 
-        let condition = condition.get_pairs();
+        let data_pos = Self::condition(query, condition);
 
-        if let Some(col) = condition {
+        if let Some(order_by) = order_by {
+            query.push_str(&format!(" ORDER BY {order_by}"));
+            tracing::trace!("6 update (query) - {query}");
+        }
+
+        if let Some(limit) = limit {
+            query.push_str(&format!(" LIMIT {limit}"));
+            tracing::trace!("6 update (query) - {query}");
+        }
+        if let Some(offset) = offset {
+            query.push_str(&format!(" OFFSET {offset}"));
+            tracing::trace!("6 update (query) - {query}");
+        }
+
+        Self::bind(query, data_pos)
+    }
+
+    /// The rows selected are locked until the transaction ends
+    pub fn get_for_update(
+        query: &mut String,
+        condition: impl MapQuery,
+    ) -> Query<'_, Postgres, PgArguments> {
+        tracing::trace!("SQL OPERATIONS");
+        tracing::trace!("1 input (query) - {}", query);
+        tracing::trace!("2 input (condition) - {:?}", condition);
+
+        let data_pos = Self::condition(query, condition);
+        query.push_str(" FOR UPDATE");
+
+        tracing::trace!("6 update (query) - {query}");
+
+        Self::bind(query, data_pos)
+    }
+
+    fn condition(query: &mut String, condition: impl MapQuery) -> Vec<TypeTable> {
+        let mut data_pos = Vec::new();
+
+        if let Some(col) = condition.get_pairs() {
             tracing::trace!("5 if (condition exists) - {:?}", col);
 
             query.push_str(" WHERE");
 
-            let mut data_pos = HashMap::new();
-
-            let mut pos = 1;
             let len = col.len() - 1;
 
             for (i, (key, value)) in col.into_iter().enumerate() {
-                if value == TypeTable::Null {
-                    query.push_str(&format!(" {key} IS NULL"));
-                } else {
-                    query.push_str(&format!(" {key} = ${pos}"));
-                    data_pos.insert(pos, value);
-                    pos += 1;
+                query.push_str(&Self::predicate(key, &value, data_pos.len() + 1));
+
+                if value != TypeTable::Null {
+                    data_pos.push(value);
                 }
 
                 if i < len {
@@ -46,25 +79,43 @@ impl SqlOperations {
             }
 
             tracing::trace!("6 update (query) - {}", query);
+        }
 
-            if let Some(limit) = limit {
-                query.push_str(&format!(" LIMIT {limit}"));
-                tracing::trace!("6 update (query) - {query}");
-            }
-            if let Some(offset) = offset {
-                query.push_str(&format!(" OFFSET {offset}"));
-                tracing::trace!("6 update (query) - {query}");
-            }
-            let mut sql = sqlx::query(query);
+        data_pos
+    }
 
-            for i in 1..pos {
-                sql = bind_query!(sql, data_pos.remove(&i).unwrap());
-            }
-            sql
-        } else {
-            sqlx::query(query)
+    /// Comparison of the column `key` with the parameter `pos`
+    fn predicate(key: &str, value: &TypeTable, pos: usize) -> String {
+        match value {
+            TypeTable::Null => format!(" {key} IS NULL"),
+            TypeTable::IpOperation(e) => format!(" {key} {} ${pos}", e.operator()),
+            // The objects are matched by their primary key, the key is the name of the filter
+            TypeTable::TagFilter(e) => e.predicate(pos),
+            TypeTable::CustomFieldsFilter(_) => format!(
+                " NOT EXISTS (SELECT 1 FROM jsonb_each_text(${pos}) AS f WHERE {key} ->> f.key IS DISTINCT FROM f.value)"
+            ),
+            _ => format!(" {key} = ${pos}"),
         }
     }
+
+    /// Assignment of the parameter `pos` to the column `key`
+    fn assignment(key: &str, value: &TypeTable, pos: usize) -> String {
+        match value {
+            TypeTable::RemoveCustomField(_) => format!(" {key} = {key} - ${pos}"),
+            _ => format!(" {key} = ${pos}"),
+        }
+    }
+
+    fn bind(query: &str, data_pos: Vec<TypeTable>) -> Query<'_, Postgres, PgArguments> {
+        let mut sql = sqlx::query(query);
+
+        for value in data_pos {
+            sql = bind_query!(sql, value);
+        }
+
+        sql
+    }
+
     pub fn insert<T>(data: T, query: &str) -> Query<'_, Postgres, PgArguments>
     where
         T: Table + std::fmt::Debug,
@@ -119,7 +170,7 @@ impl SqlOperations {
             if value == TypeTable::Null {
                 query.push_str(&format!(" {key} IS NULL"));
             } else {
-                query.push_str(&format!(" {key} = ${pos}"));
+                query.push_str(&Self::assignment(key, &value, pos));
                 pos_values.insert(pos, value);
                 pos += 1;
             }
@@ -138,10 +189,9 @@ impl SqlOperations {
             query.push_str(" WHERE");
 
             for (i, (key, value)) in condition.into_iter().enumerate() {
-                if value == TypeTable::Null {
-                    query.push_str(&format!(" {key} IS NULL"));
-                } else {
-                    query.push_str(&format!(" {key} = ${pos}"));
+                query.push_str(&Self::predicate(key, &value, pos));
+
+                if value != TypeTable::Null {
                     pos_values.insert(pos, value);
                     pos += 1;
                 }
@@ -180,10 +230,9 @@ impl SqlOperations {
 
             let len = condition.len();
             for (key, value) in condition {
-                if value == TypeTable::Null {
-                    query.push_str(&format!(" {key} IS NULL"));
-                } else {
-                    query.push_str(&format!(" {key} = ${pos}"));
+                query.push_str(&Self::predicate(key, &value, pos));
+
+                if value != TypeTable::Null {
                     pos_column.insert(pos, value);
 
                     if pos < len {
@@ -56,16 +56,37 @@ impl<'b> BuilderPgTransaction<'b> {
     ) -> TransactionResult<Vec<T>> {
         let mut transaction = self.transaction.lock().await;
         let mut query = T::query_select();
-        let query = SqlOperations::get(&mut query, condition, limit, offset);
+        let query = SqlOperations::get(&mut query, condition, T::order_by(), limit, offset);
         let mut cursor = query.fetch(&mut **transaction);
         let mut resp = Vec::new();
-        while let Some(Ok(e)) = cursor.next().await {
-            resp.push(e.into());
+        while let Some(row) = cursor.next().await {
+            resp.push(row?.into());
         }
 
         Ok(resp)
     }
 
+    /// Locks the table until the transaction ends, the other transactions can read it but not change it
+    pub async fn lock<T: Table>(&mut self) -> TransactionResult<()> {
+        let mut transaction = self.transaction.lock().await;
+        let query = format!("LOCK TABLE {} IN SHARE ROW EXCLUSIVE MODE", T::name());
+
+        sqlx::query(&query).execute(&mut **transaction).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_for_update<T: Table + From<PgRow>>(
+        &mut self,
+        condition: impl MapQuery,
+    ) -> TransactionResult<T> {
+        let mut transaction = self.transaction.lock().await;
+        let mut query = T::query_select();
+        let query = SqlOperations::get_for_update(&mut query, condition);
+
+        Ok(query.fetch_one(&mut **transaction).await?.into())
+    }
+
     pub async fn insert<T: Table>(&mut self, data: T) -> TransactionResult<QueryResult> {
         let mut transaction = self.transaction.lock().await;
         let q_insert = T::query_insert(1);
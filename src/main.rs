@@ -62,7 +62,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     services::create_default_user(&db).await?;
 
-    let state = Arc::new(AppState::new(db, Semaphore::new(1)));
+    let state = Arc::new(AppState::new(
+        db,
+        Semaphore::new(1),
+        app.addresses_mode,
+        app.thresholds,
+        app.reserve_policy,
+    ));
+
+    tokio::spawn(services::capacity::evaluator(
+        Arc::clone(&state),
+        app.capacity_interval,
+    ));
+
+    tokio::spawn(services::host_count::checker(
+        Arc::clone(&state),
+        app.recount_interval,
+        app.recount_fix,
+    ));
 
     let app = Router::new()
         .nest("/api/v1", api_v1::api_v1())
@@ -0,0 +1,53 @@
+use super::{Deserialize, FromPgRow, Serialize, Table, Updatable, Uuid};
+use crate::models::network::Network;
+use libipam::services::capacity::{Level, Thresholds};
+use macros::MapQuery;
+use time::OffsetDateTime;
+
+/// Change of the capacity level of a network, recorded by the evaluator
+#[derive(Serialize, Debug, Clone, Table, FromPgRow)]
+#[table_name("capacity_alerts")]
+#[order_by("created_at DESC")]
+pub struct CapacityAlert {
+    pub id: Uuid,
+    pub network_id: Uuid,
+    pub previous: Level,
+    pub level: Level,
+
+    /// Percentage of used hosts when the level changed
+    pub utilisation: f64,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, Debug, Default, MapQuery)]
+pub struct CapacityAlertCondition {
+    pub network_id: Option<Uuid>,
+    pub level: Option<Level>,
+}
+
+#[derive(Debug, Clone, Copy, Updatable)]
+pub struct UpdateCapacity {
+    pub capacity: Level,
+}
+
+/// Network with its utilisation over one of its thresholds
+#[derive(Debug, Serialize)]
+pub struct OverThreshold {
+    pub network: Network,
+    pub utilisation: f64,
+    pub level: Level,
+    pub thresholds: Thresholds,
+}
+
+impl CapacityAlert {
+    pub fn new(network: &Network, level: Level, utilisation: f64) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            network_id: network.id,
+            previous: network.capacity,
+            level,
+            utilisation,
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use super::{Deserialize, FromPgRow, Serialize, Table, Updatable, Uuid};
+use macros::MapQuery;
+use serde::Deserializer;
+use serde_json::{Map, Value};
+use sqlx::{
+    Postgres,
+    encode::IsNull,
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef},
+    types::Json,
+};
+use std::collections::HashMap;
+
+/// Prefix of the query params that filter by a custom field, as `cf.cost_center=1234`
+const FILTER_PREFIX: &str = "cf.";
+
+#[derive(Debug, Clone, Copy, sqlx::Type, Deserialize, Serialize, PartialEq)]
+#[sqlx(type_name = "CUSTOM_FIELD_KIND")]
+pub enum FieldKind {
+    String,
+    Int,
+    Bool,
+    Enum,
+
+    /// Calendar date as `2024-12-31`
+    Date,
+}
+
+/// Object that a custom field applies to
+#[derive(Debug, Clone, Copy, sqlx::Type, Deserialize, Serialize, PartialEq)]
+#[sqlx(type_name = "CUSTOM_FIELD_TARGET")]
+pub enum FieldTarget {
+    Network,
+    Addresses,
+    Node,
+    Vlan,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Table, FromPgRow)]
+#[table_name("custom_fields")]
+#[order_by("name")]
+pub struct CustomField {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: FieldKind,
+    pub target: FieldTarget,
+
+    /// The objects created or updated through the API need a value. The objects created by the
+    /// server, as the subnets, the allocated addresses or the networks of a split, don't need it
+    pub required: bool,
+
+    /// Allowed values of the enum fields
+    pub choices: Option<Vec<String>>,
+    pub description: Option<String>,
+}
+
+/// The kind and the target cannot be changed, the stored values would be invalid
+#[derive(Deserialize, Debug, Default, Updatable)]
+pub struct UpdateCustomField {
+    pub required: Option<bool>,
+    pub choices: Option<Vec<String>>,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, MapQuery)]
+pub struct CustomFieldCondition {
+    pub id: Option<Uuid>,
+    pub name: Option<String>,
+    pub target: Option<FieldTarget>,
+}
+
+impl CustomFieldCondition {
+    pub fn p_key(id: Uuid) -> Self {
+        Self {
+            id: Some(id),
+            ..Default::default()
+        }
+    }
+
+    pub fn target(target: FieldTarget) -> Self {
+        Self {
+            target: Some(target),
+            ..Default::default()
+        }
+    }
+}
+
+/// Values of the custom fields of an object, stored as a jsonb column
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CustomFields(pub Map<String, Value>);
+
+/// Custom fields that must have a value, compared as text with the stored values.
+///
+/// A field without value matches the objects that don't have it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomFieldsFilter(pub HashMap<String, Option<String>>);
+
+#[derive(Debug, PartialEq)]
+pub enum CustomFieldError {
+    Definition(String),
+    Unknown(String),
+    Invalid(String),
+    Required(String),
+}
+
+impl CustomField {
+    /// Checks the definition, only the enum fields have choices
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the name isn't an identifier or the choices don't match the kind
+    pub fn check(&self) -> Result<(), CustomFieldError> {
+        let valid_name = self
+            .name
+            .chars()
+            .all(|x| x.is_ascii_lowercase() || x.is_ascii_digit() || x == '_')
+            && self.name.starts_with(|x: char| x.is_ascii_lowercase());
+
+        let valid_choices = match self.kind {
+            FieldKind::Enum => self.choices.as_ref().is_some_and(|x| !x.is_empty()),
+            _ => self.choices.is_none(),
+        };
+
+        if valid_name && valid_choices {
+            Ok(())
+        } else {
+            Err(CustomFieldError::Definition(self.name.clone()))
+        }
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        match self.kind {
+            FieldKind::String => value.is_string(),
+            FieldKind::Int => value.is_i64() || value.is_u64(),
+            FieldKind::Bool => value.is_boolean(),
+            FieldKind::Enum => value
+                .as_str()
+                .is_some_and(|x| self.choices.iter().flatten().any(|y| y == x)),
+            FieldKind::Date => value.as_str().is_some_and(is_date),
+        }
+    }
+}
+
+fn is_date(value: &str) -> bool {
+    let mut parts = value.splitn(3, '-');
+
+    let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+
+    let (Ok(year), Ok(month), Ok(day)) = (year.parse(), month.parse::<u8>(), day.parse()) else {
+        return false;
+    };
+
+    time::Month::try_from(month)
+        .and_then(|month| time::Date::from_calendar_date(year, month, day))
+        .is_ok()
+}
+
+impl CustomFields {
+    /// Checks the values with the definitions of their target
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a field isn't defined, has a value of other kind or a required field is missing
+    pub fn validate(&self, definitions: &[CustomField]) -> Result<(), CustomFieldError> {
+        for (name, value) in &self.0 {
+            let field = definitions
+                .iter()
+                .find(|x| &x.name == name)
+                .ok_or(CustomFieldError::Unknown(name.clone()))?;
+
+            if !field.accepts(value) {
+                return Err(CustomFieldError::Invalid(name.clone()));
+            }
+        }
+
+        match definitions
+            .iter()
+            .find(|x| x.required && !self.0.contains_key(&x.name))
+        {
+            Some(field) => Err(CustomFieldError::Required(field.name.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl CustomFieldsFilter {
+    /// Takes the `cf.` params of a query, the filter is `None` if there isn't any
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the params aren't a map of strings
+    pub fn from_query<'de, D>(deserializer: D) -> Result<Option<Self>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let fields = HashMap::<String, String>::deserialize(deserializer)?
+            .into_iter()
+            .filter_map(|(key, value)| {
+                Some((key.strip_prefix(FILTER_PREFIX)?.to_string(), Some(value)))
+            })
+            .collect::<HashMap<_, _>>();
+
+        Ok((!fields.is_empty()).then_some(Self(fields)))
+    }
+
+    pub fn new(name: String, value: Option<String>) -> Self {
+        Self(HashMap::from([(name, value)]))
+    }
+}
+
+impl sqlx::Type<Postgres> for CustomFields {
+    fn type_info() -> PgTypeInfo {
+        <Json<Map<String, Value>> as sqlx::Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <Json<Map<String, Value>> as sqlx::Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Postgres> for CustomFields {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(Self(
+            <Json<Map<String, Value>> as sqlx::Decode<Postgres>>::decode(value)?.0,
+        ))
+    }
+}
+
+impl sqlx::Encode<'_, Postgres> for CustomFields {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <Json<&Map<String, Value>> as sqlx::Encode<Postgres>>::encode_by_ref(&Json(&self.0), buf)
+    }
+}
+
+impl std::fmt::Display for CustomFieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Definition(e) => write!(
+                f,
+                "The custom field {e} needs a lowercase name, and choices only if it's an enum"
+            ),
+            Self::Unknown(e) => write!(f, "The custom field {e} isn't defined"),
+            Self::Invalid(e) => write!(f, "The custom field {e} has an invalid value"),
+            Self::Required(e) => write!(f, "The custom field {e} is required"),
+        }
+    }
+}
+
+impl std::error::Error for CustomFieldError {}
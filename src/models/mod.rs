@@ -1,7 +1,11 @@
+pub mod capacity;
+pub mod custom_field;
 pub mod network;
 pub mod node;
+pub mod tag;
 pub mod user;
 pub mod vlan;
+pub mod vrf;
 
 use macros::{FromPgRow, Table, Updatable};
 use serde::{Deserialize, Serialize};
@@ -1,25 +1,57 @@
 use super::{Deserialize, FromPgRow, Serialize, Table};
+use crate::{
+    database::repository::IpOperation,
+    models::{
+        custom_field::{CustomFields, CustomFieldsFilter},
+        tag::TagFilter,
+    },
+};
 use ipnet::IpNet;
+use libipam::{
+    services::{
+        allocation::Strategy,
+        ipam::{ip_to_u128, u128_to_ip},
+    },
+    types::host_count::HostCount,
+};
 use macros::{MapQuery, Updatable};
-use std::net::{IpAddr, Ipv4Addr};
+use std::{net::IpAddr, ops::Range};
 use uuid::Uuid;
 
 #[derive(Debug, Deserialize, Serialize, Clone, Table, FromPgRow)]
+#[order_by("ip")]
 pub struct Addresses {
-    #[FromStr]
     pub ip: IpNet,
 
     pub network_id: Uuid,
     pub status: StatusAddr,
     pub node_id: Option<Uuid>,
+
+    #[serde(default)]
+    pub custom_fields: CustomFields,
+
+    /// Why the address is reserved, as the gateway reserved by the policy of the network
+    pub label: Option<String>,
 }
 
-#[derive(Debug, MapQuery, Default, Clone, Updatable, Deserialize)]
+#[derive(Debug, MapQuery, Default, Clone)]
 pub struct AddrCondition {
     pub ip: Option<IpNet>,
     pub network_id: Option<Uuid>,
     pub node_id: Option<Uuid>,
     pub status: Option<StatusAddr>,
+    pub custom_fields: Option<CustomFieldsFilter>,
+    pub tags: Option<TagFilter>,
+}
+
+#[derive(Debug, Default, Clone, Updatable, Deserialize)]
+pub struct UpdateAddr {
+    pub ip: Option<IpNet>,
+    pub network_id: Option<Uuid>,
+    pub node_id: Option<Uuid>,
+    pub status: Option<StatusAddr>,
+    pub custom_fields: Option<CustomFields>,
+    pub label: Option<String>,
 }
 
 impl AddrCondition {
@@ -32,6 +64,14 @@ impl AddrCondition {
     }
 }
 
+/// Condition of the addresses by their ip, as the addresses of a network with its subnets
+#[derive(Debug, MapQuery, Default, Clone)]
+pub struct AddrIpCondition {
+    pub ip: Option<IpOperation>,
+    pub network_id: Option<Uuid>,
+    pub status: Option<StatusAddr>,
+}
+
 #[derive(Debug, Deserialize, Serialize, sqlx::Type, PartialEq, Clone, Copy, Default)]
 pub enum StatusAddr {
     Reserved,
@@ -44,11 +84,65 @@ pub enum StatusAddr {
     Reachable,
 }
 
+impl StatusAddr {
+    /// Statuses of the addresses that are counted as used hosts
+    pub const USED: [Self; 4] = [Self::Reserved, Self::Online, Self::Offline, Self::Reachable];
+}
+
+/// Defines which addresses are written in the database.
+///
+/// - `Dense`: every host of a network can be stored, as `create_all_ip_addresses` does
+/// - `Sparse`: only the addresses with a status other than `Unknown` or with a node are stored,
+///   the rest of them are synthesised from the network's range when they're listed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AddrMode {
+    #[default]
+    Dense,
+    Sparse,
+}
+
+impl std::str::FromStr for AddrMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dense" => Ok(Self::Dense),
+            "sparse" => Ok(Self::Sparse),
+            e => Err(format!("Invalid addresses mode {e}")),
+        }
+    }
+}
+
+impl Addresses {
+    /// An address without status, node nor custom fields doesn't need to be stored in sparse mode
+    pub fn is_stored(&self, mode: AddrMode) -> bool {
+        mode == AddrMode::Dense
+            || self.status != StatusAddr::Unknown
+            || self.node_id.is_some()
+            || !self.custom_fields.0.is_empty()
+    }
+
+    /// An address reserved by the policy of its network, as the gateway, only has a label.
+    ///
+    /// It's counted as used, but the network isn't in use because of it
+    pub fn is_policy_reserved(&self) -> bool {
+        self.status == StatusAddr::Reserved
+            && self.label.is_some()
+            && self.node_id.is_none()
+            && self.custom_fields.0.is_empty()
+    }
+}
+
+/// The usable hosts of a network, they're the same hosts counted by `HostCount`
+///
+/// The addresses are calculated from their index, so the range of an ipv6 network
+/// is never collected in memory
 #[derive(Debug)]
 pub struct AddrRange {
-    start: u32,
-    end: u32,
-    step: u32,
+    start: u128,
+    len: u128,
+    step: u128,
+    ipv4: bool,
     pub network_id: Uuid,
     prefix: u8,
 }
@@ -61,13 +155,9 @@ pub struct BatchAddr {
 
 impl BatchAddr {
     fn new(iter: AddrRange, n: usize) -> Self {
-        let n = n.min(iter.len());
+        let n = usize::try_from(iter.len()).map_or(n, |len| n.min(len));
         Self { iter, window: n }
     }
-
-    pub fn inner_len(&self) -> usize {
-        self.iter.len()
-    }
 }
 
 impl Iterator for BatchAddr {
@@ -81,72 +171,252 @@ impl Iterator for BatchAddr {
     }
 }
 
-impl ExactSizeIterator for BatchAddr {
-    fn len(&self) -> usize {
-        self.iter.len().div_ceil(self.window)
-    }
-}
-
 impl AddrRange {
-    pub fn new_with_uuid(network: IpNet, network_id: Uuid) -> Result<Self, AddrRangeError> {
-        let start = match network.network() {
-            IpAddr::V4(e) => u32::from(e),
-            IpAddr::V6(_) => return Err(AddrRangeError::InvalidNetwork),
-        };
-
-        let host = 2u32.pow(u32::from(network.max_prefix_len() - network.prefix_len())) - 2;
+    pub fn new_with_uuid(network: IpNet, network_id: Uuid) -> Self {
+        let start = ip_to_u128(network.network());
 
-        Ok(AddrRange {
-            start,
-            end: start + host,
+        AddrRange {
+            // The network and broadcast addresses aren't hosts, except in a /31 or /32
+            start: if network.max_prefix_len() - network.prefix_len() > 1 {
+                start + 1
+            } else {
+                start
+            },
+            len: HostCount::from(network).as_u128(),
+            step: 0,
+            ipv4: network.network().is_ipv4(),
             network_id,
             prefix: network.prefix_len(),
-            step: 0,
-        })
+        }
     }
 
     pub fn batch(self, n: usize) -> BatchAddr {
         BatchAddr::new(self, n)
     }
-}
 
-impl Iterator for AddrRange {
-    type Item = Addresses;
+    /// Number of addresses that haven't been iterated yet
+    pub fn len(&self) -> u128 {
+        self.len - self.step
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        (self.end > self.start + self.step).then(|| {
-            self.step += 1;
-            Addresses {
-                ip: IpNet::new(
-                    IpAddr::from(Ipv4Addr::from(self.start + self.step)),
-                    self.prefix,
-                )
-                .unwrap(),
-                status: StatusAddr::default(),
-                network_id: self.network_id,
-                node_id: None,
-            }
+    /// Position of the ip inside the range, `None` if the ip isn't an usable host
+    pub fn index_of(&self, ip: IpAddr) -> Option<u128> {
+        (ip.is_ipv4() == self.ipv4)
+            .then(|| ip_to_u128(ip).checked_sub(self.start))
+            .flatten()
+            .filter(|x| *x < self.len)
+    }
+
+    /// The address in the position `index`, without status nor node
+    pub fn get(&self, index: u128) -> Option<Addresses> {
+        (index < self.len).then(|| Addresses {
+            ip: IpNet::new(u128_to_ip(self.start + index, self.ipv4), self.prefix).unwrap(),
+            status: StatusAddr::default(),
+            network_id: self.network_id,
+            node_id: None,
+            custom_fields: CustomFields::default(),
+            label: None,
         })
     }
-}
 
-impl ExactSizeIterator for AddrRange {
-    fn len(&self) -> usize {
-        (self.end - self.start) as usize
+    /// `n` positions of `positions` that aren't in `taken` picked by `strategy`, fewer if they're exhausted
+    pub fn free(
+        &self,
+        strategy: Strategy,
+        taken: Vec<u128>,
+        n: usize,
+        positions: Range<u128>,
+    ) -> Vec<u128> {
+        strategy.pick(taken, n, positions.start..positions.end.min(self.len))
     }
 }
 
+/// One page of the addresses of a range, the stored addresses are pushed in the order of their ip
+/// and replace the synthesised ones.
+///
+/// With `skip_used` the stored addresses with a status aren't part of the page nor counted by the
+/// offset, so every stored address before the page is read to find where the page starts
 #[derive(Debug)]
-pub enum AddrRangeError {
-    InvalidNetwork,
+pub struct AddrPage<'a> {
+    range: &'a AddrRange,
+    index: u128,
+    offset: u128,
+    limit: usize,
+    skip_used: bool,
+    data: Vec<Addresses>,
 }
 
-impl std::fmt::Display for AddrRangeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            AddrRangeError::InvalidNetwork => write!(f, "Only support ipv4 network"),
+impl<'a> AddrPage<'a> {
+    pub fn new(range: &'a AddrRange, offset: u128, limit: usize, skip_used: bool) -> Self {
+        // Without skipped addresses the position of the page is known
+        let (index, offset) = if skip_used { (0, offset) } else { (offset, 0) };
+
+        Self {
+            range,
+            index,
+            offset,
+            limit,
+            skip_used,
+            data: Vec::new(),
+        }
+    }
+
+    /// The ip from which the stored addresses are still needed, `None` once the page is complete
+    pub fn next_ip(&self) -> Option<IpNet> {
+        if self.data.len() < self.limit {
+            self.range.get(self.index).map(|x| x.ip)
+        } else {
+            None
+        }
+    }
+
+    /// Pushes the synthesised addresses before the stored address, and the stored address
+    pub fn push(&mut self, addr: Addresses) {
+        let Some(index) = self.range.index_of(addr.ip.addr()) else {
+            return;
+        };
+
+        if index < self.index {
+            return;
+        }
+
+        self.synthesise(index);
+
+        if self.data.len() >= self.limit {
+            return;
+        }
+
+        self.index = index + 1;
+
+        if self.skip_used && addr.status != StatusAddr::Unknown {
+            return;
+        }
+
+        if self.offset > 0 {
+            self.offset -= 1;
+        } else {
+            self.data.push(addr);
+        }
+    }
+
+    /// Completes the page with the synthesised addresses, there aren't more stored addresses
+    pub fn finish(mut self) -> Vec<Addresses> {
+        self.synthesise(self.range.len);
+        self.data
+    }
+
+    fn synthesise(&mut self, end: u128) {
+        let skip = self.offset.min(end.saturating_sub(self.index));
+        self.offset -= skip;
+        self.index += skip;
+
+        while self.data.len() < self.limit && self.index < end {
+            self.data.extend(self.range.get(self.index));
+            self.index += 1;
         }
     }
 }
 
-impl std::error::Error for AddrRangeError {}
+impl Iterator for AddrRange {
+    type Item = Addresses;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let resp = self.get(self.step)?;
+        self.step += 1;
+        Some(resp)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.step = self.step.saturating_add(n as u128).min(self.len);
+        self.next()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn range() -> AddrRange {
+        AddrRange::new_with_uuid("10.0.0.0/28".parse().unwrap(), Uuid::nil())
+    }
+
+    fn stored(ip: &str, status: StatusAddr) -> Addresses {
+        Addresses {
+            ip: format!("{ip}/28").parse().unwrap(),
+            status,
+            ..range().get(0).unwrap()
+        }
+    }
+
+    fn ips(data: &[Addresses]) -> Vec<String> {
+        data.iter().map(|x| x.ip.addr().to_string()).collect()
+    }
+
+    #[test]
+    fn page_offset_over_stored_and_synthesised() {
+        let range = range();
+        let mut page = AddrPage::new(&range, 2, 4, false);
+
+        assert_eq!(page.next_ip(), Some("10.0.0.3/28".parse().unwrap()));
+
+        // The stored addresses before the page are ignored
+        page.push(stored("10.0.0.2", StatusAddr::Online));
+        page.push(stored("10.0.0.4", StatusAddr::Reserved));
+
+        let data = page.finish();
+
+        assert_eq!(
+            ips(&data),
+            vec!["10.0.0.3", "10.0.0.4", "10.0.0.5", "10.0.0.6"]
+        );
+        assert_eq!(data[0].status, StatusAddr::Unknown);
+        assert_eq!(data[1].status, StatusAddr::Reserved);
+    }
+
+    #[test]
+    fn page_skip_used() {
+        let range = range();
+        let mut page = AddrPage::new(&range, 1, 3, true);
+
+        // The used addresses aren't counted by the offset, the synthesised 10.0.0.2 is
+        page.push(stored("10.0.0.1", StatusAddr::Online));
+        page.push(stored("10.0.0.3", StatusAddr::Reserved));
+        page.push(stored("10.0.0.4", StatusAddr::Unknown));
+
+        assert_eq!(page.next_ip(), Some("10.0.0.5/28".parse().unwrap()));
+
+        let data = page.finish();
+
+        assert_eq!(ips(&data), vec!["10.0.0.4", "10.0.0.5", "10.0.0.6"]);
+        assert!(data.iter().all(|x| x.status == StatusAddr::Unknown));
+    }
+
+    #[test]
+    fn page_last_partial() {
+        let range = range();
+        let mut page = AddrPage::new(&range, 12, 5, false);
+
+        page.push(stored("10.0.0.14", StatusAddr::Online));
+
+        let data = page.finish();
+
+        assert_eq!(ips(&data), vec!["10.0.0.13", "10.0.0.14"]);
+        assert_eq!(data[1].status, StatusAddr::Online);
+
+        let page = AddrPage::new(&range, 20, 5, false);
+
+        assert_eq!(page.next_ip(), None);
+        assert!(page.finish().is_empty());
+    }
+
+    #[test]
+    fn page_complete() {
+        let range = range();
+        let mut page = AddrPage::new(&range, 0, 2, false);
+
+        page.push(stored("10.0.0.2", StatusAddr::Online));
+
+        assert_eq!(page.next_ip(), None);
+        assert_eq!(ips(&page.finish()), vec!["10.0.0.1", "10.0.0.2"]);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use super::{Network, Serialize, Updatable, Uuid};
+use ipnet::IpNet;
+use libipam::types::host_count::HostCount;
+
+/// Counters of a network, as they're updated by `update_host_count` and `update_children`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Updatable)]
+pub struct Counters {
+    pub used: HostCount,
+    pub free: HostCount,
+    pub children: i32,
+}
+
+/// Network whose stored counters aren't the counters of its rows
+#[derive(Debug, Serialize)]
+pub struct HostCountDrift {
+    pub network_id: Uuid,
+    pub subnet: IpNet,
+    pub stored: Counters,
+    pub expected: Counters,
+}
+
+impl Network {
+    pub fn counters(&self) -> Counters {
+        Counters {
+            used: self.used,
+            free: self.free,
+            children: self.children,
+        }
+    }
+}
+
+/// Counts again the counters of every network.
+///
+/// The used hosts of a network are its descendant networks and the used addresses of the network
+/// and of its descendants, `used` has the used addresses of each network
+pub fn recount(networks: &[Network], used: &HashMap<Uuid, u128>) -> Vec<HostCountDrift> {
+    let mut children = HashMap::<Uuid, Vec<Uuid>>::new();
+
+    for network in networks {
+        if let Some(father) = network.father {
+            children.entry(father).or_default().push(network.id);
+        }
+    }
+
+    let mut subtree = HashMap::new();
+
+    networks
+        .iter()
+        .filter_map(|network| {
+            let used = used_hosts(network.id, &children, used, &mut subtree);
+
+            let expected = Counters {
+                used: HostCount::from(used),
+                free: HostCount::new_from_ipnet_with_sub(network.subnet, used).unwrap_or_default(),
+                children: children
+                    .get(&network.id)
+                    .map_or(0, |x| x.len().try_into().unwrap_or(i32::MAX)),
+            };
+
+            (expected != network.counters()).then(|| HostCountDrift {
+                network_id: network.id,
+                subnet: network.subnet,
+                stored: network.counters(),
+                expected,
+            })
+        })
+        .collect()
+}
+
+/// Used hosts of the network `id`, the result of each network is kept in `subtree`
+fn used_hosts(
+    id: Uuid,
+    children: &HashMap<Uuid, Vec<Uuid>>,
+    used: &HashMap<Uuid, u128>,
+    subtree: &mut HashMap<Uuid, u128>,
+) -> u128 {
+    if let Some(n) = subtree.get(&id) {
+        return *n;
+    }
+
+    let n = children.get(&id).into_iter().flatten().fold(
+        used.get(&id).copied().unwrap_or_default(),
+        |acc, child| {
+            acc.saturating_add(1)
+                .saturating_add(used_hosts(*child, children, used, subtree))
+        },
+    );
+
+    subtree.insert(id, n);
+    n
+}
@@ -1,24 +1,43 @@
 pub mod addresses;
+pub mod host_count;
+pub mod scope;
+pub mod status;
+pub mod tree;
 
 use super::{Deserialize, FromPgRow, Serialize, Table, Updatable, Uuid};
-use addresses::{AddrRange, AddrRangeError};
+use crate::{
+    database::repository::{IpOperation, Nullable},
+    models::{
+        custom_field::{CustomFields, CustomFieldsFilter},
+        tag::TagFilter,
+    },
+};
+use addresses::{AddrRange, Addresses, StatusAddr};
 use ipnet::IpNet;
 use libipam::{
-    services::ipam::{SubnetList, SubnettingError},
-    types::{
-        host_count::{HostCount, Operation},
-        vlan::VlanId,
+    services::{
+        allocation::Strategy,
+        capacity::{Level, Thresholds},
+        ipam::{SubnetList, SubnettingError},
+        reservation::ReservePolicy,
     },
+    types::{host_count::HostCount, vlan::VlanId},
 };
 use macros::MapQuery;
 
 #[derive(Debug, MapQuery, Default, Clone)]
 pub struct NetwCondition {
     pub id: Option<Uuid>,
+    pub subnet: Option<IpOperation>,
     pub description: Option<String>,
     pub status: Option<StatusNetwork>,
     pub father: Option<Uuid>,
     pub kind: Option<Kind>,
+
+    /// The networks without vrf belong to the global vrf
+    pub vrf: Option<Nullable<Uuid>>,
+    pub custom_fields: Option<CustomFieldsFilter>,
+    pub tags: Option<TagFilter>,
 }
 
 impl NetwCondition {
@@ -32,17 +51,26 @@ impl NetwCondition {
 
 #[derive(Debug, Deserialize, Serialize, Updatable)]
 pub struct UpdateNetwork {
+    /// The subnet is validated against the other networks, it's updated with `UpdateSubnet`
+    #[IgnoreFieldToUpdate]
     pub network: Option<IpNet>,
     pub description: Option<String>,
     pub vlan: Option<VlanId>,
+    pub custom_fields: Option<CustomFields>,
+    pub warning_threshold: Option<i16>,
+    pub critical_threshold: Option<i16>,
+
+    /// The policy is applied to the new subnets and when every address is created
+    pub reserve_first: Option<i16>,
+    pub reserve_last: Option<i16>,
+    pub allocation: Option<Strategy>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Table, FromPgRow)]
 #[table_name("networks")]
+#[order_by("subnet")]
 pub struct Network {
     pub id: Uuid,
-
-    #[FromStr]
     pub subnet: IpNet,
 
     pub used: HostCount,
@@ -53,6 +81,43 @@ pub struct Network {
     pub children: i32,
     pub status: StatusNetwork,
     pub kind: Kind,
+    pub vrf: Option<Uuid>,
+
+    #[serde(default)]
+    pub custom_fields: CustomFields,
+
+    /// Percentages of used hosts, the network uses the global thresholds without them
+    pub warning_threshold: Option<i16>,
+    pub critical_threshold: Option<i16>,
+
+    /// Level of the last evaluation of the thresholds
+    #[serde(default)]
+    pub capacity: Level,
+
+    /// Hosts reserved at the start and at the end, the network uses the global policy without them
+    pub reserve_first: Option<i16>,
+    pub reserve_last: Option<i16>,
+
+    /// Strategy of the allocated addresses, each request can use another one
+    #[serde(default)]
+    pub allocation: Strategy,
+}
+
+/// Networks and addresses that are removed with a network
+#[derive(Debug, Serialize)]
+pub struct DeleteImpact {
+    pub networks: Vec<Network>,
+    pub addresses: Vec<Addresses>,
+}
+
+/// The most specific network that contains an address, with its ancestors up to the root
+#[derive(Debug, Serialize)]
+pub struct Lookup {
+    pub network: Network,
+    pub ancestors: Vec<Network>,
+
+    /// The stored address, or the unknown address if it's a host of the network
+    pub address: Option<Addresses>,
 }
 
 #[derive(Debug, Clone, Copy, Updatable)]
@@ -69,42 +134,44 @@ impl UpdateHostCount {
         Self { subnet, used, free }
     }
 
-    pub fn less_free_more_used(&mut self, n: i32) {
-        // This AddAssign trait's method is infallible
-        // we never want the value exeeds the maximum allowed
-        if !self.used.is_max() {
-            // if the value is maximum allowed we do not need to increase it
-            self.used += n;
-        }
-
-        self.free = match HostCount::new_with_operation_with_ipnet(self.subnet, Operation::Sub(n)) {
-            Err(e) => {
-                tracing::error!("{e}");
-                // The method from SubAssign trait is infallible; if the result is an underflow, the value is truncated to 0
-                // we always need one value, even it is incorrect
-                // However, even though the value is incorrect, we are still going to write an entry.
-                self.free - n // We decrease this value because it's not necessarily zero.
-            }
-            Ok(e) => e,
-        };
+    pub fn less_free_more_used(&mut self, n: u128) {
+        // The counters are exact, so an error here means that the stored values are already wrong.
+        // In that case the value is truncated, as we always need to write one
+        self.used = self.used.checked_add(n).unwrap_or_else(|e| {
+            tracing::error!("used hosts of {}: {e}", self.subnet);
+            HostCount::new_max()
+        });
+
+        self.free = self.free.checked_sub(n).unwrap_or_else(|e| {
+            tracing::error!("free hosts of {}: {e}", self.subnet);
+            HostCount::default()
+        });
     }
 
-    pub fn less_used_more_free(&mut self, n: i32) {
-        self.used = match HostCount::new_with_operation_with_ipnet(self.subnet, Operation::Sub(n)) {
-            Ok(e) => e,
-            Err(e) => {
-                tracing::error!("{e}");
-                self.free - n
-            }
-        };
-
-        if !self.free.is_max() {
-            // if the value is maximum allowed we do not need to increase it
-            self.free += n;
-        }
+    pub fn less_used_more_free(&mut self, n: u128) {
+        self.used = self.used.checked_sub(n).unwrap_or_else(|e| {
+            tracing::error!("used hosts of {}: {e}", self.subnet);
+            HostCount::default()
+        });
+
+        self.free = self.free.checked_add(n).unwrap_or_else(|e| {
+            tracing::error!("free hosts of {}: {e}", self.subnet);
+            HostCount::from(self.subnet)
+        });
     }
 }
 
+#[derive(Debug, Clone, Copy, Updatable)]
+pub struct UpdateChildren {
+    children: i32,
+}
+
+#[derive(Debug, Clone, Copy, Updatable)]
+pub struct UpdateSubnet {
+    subnet: IpNet,
+    free: HostCount,
+}
+
 pub struct NetworkSubnetList {
     iter: SubnetList,
     default: DefaultValuesNetwork,
@@ -137,7 +204,7 @@ impl std::iter::Iterator for NetworkSubnetList {
         Some(Network {
             id: Uuid::new_v4(),
             subnet: net,
-            used: 0.try_into().unwrap(),
+            used: HostCount::default(),
             free: avl,
             vlan: None,
             description: self.default.description.clone(),
@@ -145,6 +212,14 @@ impl std::iter::Iterator for NetworkSubnetList {
             children: 0,
             status: self.default.status.unwrap_or_default(),
             kind: self.default.kind.unwrap_or_default(),
+            vrf: self.default.vrf,
+            custom_fields: CustomFields::default(),
+            warning_threshold: None,
+            critical_threshold: None,
+            capacity: Level::default(),
+            reserve_first: self.default.reserve_first,
+            reserve_last: self.default.reserve_last,
+            allocation: Strategy::default(),
         })
     }
 }
@@ -158,9 +233,12 @@ impl std::iter::ExactSizeIterator for NetworkSubnetList {
 #[derive(Default)]
 pub struct DefaultValuesNetwork {
     pub father: Option<Uuid>,
+    pub vrf: Option<Uuid>,
     pub status: Option<StatusNetwork>,
     pub kind: Option<Kind>,
     pub description: Option<String>,
+    pub reserve_first: Option<i16>,
+    pub reserve_last: Option<i16>,
 }
 
 impl DefaultValuesNetwork {
@@ -172,9 +250,12 @@ impl DefaultValuesNetwork {
     ) -> Self {
         Self {
             father: Some(father),
+            vrf: None,
             status,
             kind,
             description,
+            reserve_first: None,
+            reserve_last: None,
         }
     }
 }
@@ -215,7 +296,10 @@ pub enum Kind {
     Network,
 }
 
-#[derive(Debug, Clone, Copy, sqlx::Type, Deserialize, Serialize, PartialEq, Default)]
+/// Status of a network, the variants are ordered by its lifecycle
+#[derive(
+    Debug, Clone, Copy, sqlx::Type, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Default,
+)]
 #[sqlx(type_name = "STATUS_NETWORK")]
 pub enum StatusNetwork {
     #[default]
@@ -257,7 +341,7 @@ impl From<IpNet> for Network {
         Self {
             subnet: value,
             id: uuid::Uuid::new_v4(),
-            used: 0.try_into().unwrap(),
+            used: HostCount::default(),
             free: avl,
             vlan: None,
             description: None,
@@ -265,12 +349,20 @@ impl From<IpNet> for Network {
             children: 0,
             status: StatusNetwork::default(),
             kind: Kind::default(),
+            vrf: None,
+            custom_fields: CustomFields::default(),
+            warning_threshold: None,
+            critical_threshold: None,
+            capacity: Level::default(),
+            reserve_first: None,
+            reserve_last: None,
+            allocation: Strategy::default(),
         }
     }
 }
 
 impl Network {
-    pub fn addresses(&self) -> Result<AddrRange, AddrRangeError> {
+    pub fn addresses(&self) -> AddrRange {
         AddrRange::new_with_uuid(self.subnet, self.id)
     }
 
@@ -281,7 +373,80 @@ impl Network {
         ))
     }
 
+    /// Thresholds of the network, the global thresholds are used if its own aren't valid
+    pub fn thresholds(&self, global: Thresholds) -> Thresholds {
+        global
+            .with(self.warning_threshold, self.critical_threshold)
+            .unwrap_or(global)
+    }
+
+    /// Reservation policy of the network, the global policy is used if its own isn't valid
+    pub fn reserve_policy(&self, global: ReservePolicy) -> ReservePolicy {
+        global
+            .with(self.reserve_first, self.reserve_last)
+            .unwrap_or(global)
+    }
+
+    /// Addresses reserved by the policy of the network, with their labels
+    pub fn reserved(&self, global: ReservePolicy) -> Vec<Addresses> {
+        let range = self.addresses();
+
+        self.reserve_policy(global)
+            .positions(range.len())
+            .into_iter()
+            .filter_map(|(index, label)| {
+                range.get(index).map(|addr| Addresses {
+                    status: StatusAddr::Reserved,
+                    label: Some(label),
+                    ..addr
+                })
+            })
+            .collect()
+    }
+
+    /// A new network of `subnet` with the settings of `self`, without used hosts nor children
+    pub fn with_subnet(&self, subnet: IpNet) -> Self {
+        Self {
+            vlan: self.vlan,
+            description: self.description.clone(),
+            father: self.father,
+            status: self.status,
+            kind: self.kind,
+            vrf: self.vrf,
+            custom_fields: self.custom_fields.clone(),
+            warning_threshold: self.warning_threshold,
+            critical_threshold: self.critical_threshold,
+            capacity: self.capacity,
+            reserve_first: self.reserve_first,
+            reserve_last: self.reserve_last,
+            allocation: self.allocation,
+            ..Self::from(subnet)
+        }
+    }
+
+    /// Counts `n` hosts of a new network as used, as its reserved addresses
+    pub fn set_used(&mut self, n: u128) {
+        self.used = HostCount::from(n);
+        self.free = HostCount::new_from_ipnet_with_sub(self.subnet, n).unwrap_or_default();
+    }
+
     pub fn update_host_count(&self) -> UpdateHostCount {
         UpdateHostCount::new(self.subnet, self.used, self.free)
     }
+
+    /// Updater of the subnet, the free hosts are calculated again with the current used hosts
+    pub fn update_subnet(&self, subnet: IpNet) -> UpdateSubnet {
+        UpdateSubnet {
+            subnet,
+            free: HostCount::new_from_ipnet_with_sub(subnet, self.used.as_u128())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Updater of the children counter, `n` is added to the current value
+    pub fn update_children(&self, n: i32) -> UpdateChildren {
+        UpdateChildren {
+            children: self.children.saturating_add(n).max(0),
+        }
+    }
 }
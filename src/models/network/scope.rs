@@ -0,0 +1,75 @@
+use super::{Deserialize, FromPgRow, Serialize, Table, Updatable, Uuid};
+use libipam::{services::ipam::ip_to_u128, types::host_count::HostCount};
+use macros::MapQuery;
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Copy, sqlx::Type, Deserialize, Serialize, PartialEq)]
+#[sqlx(type_name = "SCOPE_PURPOSE")]
+pub enum ScopePurpose {
+    Dhcp,
+    Static,
+    Reserved,
+}
+
+/// Range of addresses of a pool, both ends are included
+#[derive(Deserialize, Serialize, Debug, Clone, Table, FromPgRow)]
+#[table_name("scopes")]
+#[order_by("start_ip")]
+pub struct Scope {
+    pub id: Uuid,
+    pub network_id: Uuid,
+    pub start_ip: IpAddr,
+    pub end_ip: IpAddr,
+    pub purpose: ScopePurpose,
+    pub description: Option<String>,
+}
+
+/// The ends of a scope aren't updated, the scope is created again instead
+#[derive(Deserialize, Debug, Default, Updatable)]
+pub struct UpdateScope {
+    pub purpose: Option<ScopePurpose>,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, MapQuery)]
+pub struct ScopeCondition {
+    pub id: Option<Uuid>,
+    pub network_id: Option<Uuid>,
+    pub purpose: Option<ScopePurpose>,
+}
+
+/// Scope with the addresses used and free inside it
+#[derive(Debug, Serialize)]
+pub struct ScopeUsage {
+    #[serde(flatten)]
+    pub scope: Scope,
+    pub used: HostCount,
+    pub free: HostCount,
+}
+
+impl ScopeCondition {
+    pub fn network(network_id: Uuid) -> Self {
+        Self {
+            network_id: Some(network_id),
+            ..Default::default()
+        }
+    }
+}
+
+impl Scope {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        ip.is_ipv4() == self.start_ip.is_ipv4()
+            && (ip_to_u128(self.start_ip)..=ip_to_u128(self.end_ip)).contains(&ip_to_u128(ip))
+    }
+
+    pub fn overlaps(&self, other: &Scope) -> bool {
+        self.start_ip.is_ipv4() == other.start_ip.is_ipv4()
+            && ip_to_u128(self.start_ip) <= ip_to_u128(other.end_ip)
+            && ip_to_u128(other.start_ip) <= ip_to_u128(self.end_ip)
+    }
+
+    /// Number of addresses of the scope
+    pub fn len(&self) -> u128 {
+        (ip_to_u128(self.end_ip) - ip_to_u128(self.start_ip)).saturating_add(1)
+    }
+}
@@ -0,0 +1,64 @@
+use super::{Deserialize, FromPgRow, Serialize, StatusNetwork, Table, Updatable, Uuid};
+use macros::MapQuery;
+use time::OffsetDateTime;
+
+/// Change of the status of a network, with the user that changed it
+#[derive(Serialize, Debug, Clone, Table, FromPgRow)]
+#[table_name("network_status_history")]
+#[order_by("created_at DESC")]
+pub struct StatusTransition {
+    pub id: Uuid,
+    pub network_id: Uuid,
+    pub previous: StatusNetwork,
+    pub status: StatusNetwork,
+    pub reason: Option<String>,
+
+    /// The history is kept when the user is removed
+    pub user_id: Option<Uuid>,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, Debug, Default, MapQuery)]
+pub struct StatusTransitionCondition {
+    pub network_id: Option<Uuid>,
+    pub status: Option<StatusNetwork>,
+    pub user_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, Updatable)]
+pub struct UpdateStatus {
+    pub status: StatusNetwork,
+}
+
+impl StatusNetwork {
+    /// The lifecycle goes from `Available` to `Used`, a network can always move forward.
+    ///
+    /// A network in use, with subnets or used addresses, only goes back to `Assigned`
+    pub fn can_transition(self, to: Self, in_use: bool) -> bool {
+        match to.cmp(&self) {
+            std::cmp::Ordering::Equal => false,
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Less => to == Self::Assigned || !in_use,
+        }
+    }
+}
+
+impl StatusTransition {
+    pub fn new(
+        network_id: Uuid,
+        previous: StatusNetwork,
+        status: StatusNetwork,
+        reason: Option<String>,
+        user_id: Uuid,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            network_id,
+            previous,
+            status,
+            reason,
+            user_id: Some(user_id),
+            created_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use super::{Network, Serialize, Uuid};
+
+/// Network with its subnets nested, as they are stored by the `father` column
+#[derive(Debug, Serialize)]
+pub struct NetworkTree {
+    #[serde(flatten)]
+    pub network: Network,
+
+    /// Percent of the hosts that are used
+    pub utilization: f64,
+    pub subnets: Vec<NetworkTree>,
+}
+
+impl NetworkTree {
+    /// Builds the trees whose roots are `root`, or the networks without father if it's `None`.
+    ///
+    /// The levels below `depth` are discarded, and a network that doesn't match the `filter` is
+    /// only kept if one of its subnets matches it
+    pub fn build<F>(
+        networks: Vec<Network>,
+        root: Option<Uuid>,
+        depth: Option<u32>,
+        filter: F,
+    ) -> Vec<NetworkTree>
+    where
+        F: Fn(&Network) -> bool,
+    {
+        let mut children = HashMap::<Option<Uuid>, Vec<Network>>::new();
+        let mut roots = Vec::new();
+
+        for network in networks {
+            if root.is_some_and(|x| x == network.id) {
+                roots.push(network);
+            } else {
+                children.entry(network.father).or_default().push(network);
+            }
+        }
+
+        if root.is_none() {
+            roots = children.remove(&None).unwrap_or_default();
+        }
+
+        roots
+            .into_iter()
+            .filter_map(|x| Self::node(x, &mut children, depth, &filter))
+            .collect()
+    }
+
+    fn node<F>(
+        network: Network,
+        children: &mut HashMap<Option<Uuid>, Vec<Network>>,
+        depth: Option<u32>,
+        filter: &F,
+    ) -> Option<NetworkTree>
+    where
+        F: Fn(&Network) -> bool,
+    {
+        let subnets = match depth {
+            Some(0) => Vec::new(),
+            depth => children
+                .remove(&Some(network.id))
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|x| Self::node(x, children, depth.map(|d| d - 1), filter))
+                .collect::<Vec<_>>(),
+        };
+
+        if subnets.is_empty() && !filter(&network) {
+            return None;
+        }
+
+        let used = network.used.as_u128() as f64;
+        let total = used + network.free.as_u128() as f64;
+        let utilization = if total > 0.0 {
+            (used * 10000.0 / total).round() / 100.0
+        } else {
+            0.0
+        };
+
+        Some(NetworkTree {
+            network,
+            utilization,
+            subnets,
+        })
+    }
+}
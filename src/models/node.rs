@@ -1,4 +1,8 @@
-use super::{Deserialize, Serialize, Table, Updatable, Uuid};
+use super::{
+    Deserialize, Serialize, Table, Updatable, Uuid,
+    custom_field::{CustomFields, CustomFieldsFilter},
+    tag::TagFilter,
+};
 use macros::{FromPgRow, MapQuery};
 
 #[derive(Deserialize, Serialize, Debug, Updatable, Default)]
@@ -10,6 +14,7 @@ pub struct UpdateNode {
     pub mount_point: Option<String>,
     pub username: Option<String>,
     pub password: Option<String>,
+    pub custom_fields: Option<CustomFields>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, FromPgRow, Table)]
@@ -23,6 +28,9 @@ pub struct Node {
     pub mount_point: Option<String>,
     pub username: Option<String>,
     pub password: Option<String>,
+
+    #[serde(default)]
+    pub custom_fields: CustomFields,
 }
 
 #[derive(Debug, Deserialize, Clone, MapQuery, Default)]
@@ -34,6 +42,12 @@ pub struct NodeCondition {
     pub label: Option<String>,
     pub room_name: Option<Uuid>,
     pub mount_point: Option<String>,
+
+    #[serde(flatten, deserialize_with = "CustomFieldsFilter::from_query")]
+    pub custom_fields: Option<CustomFieldsFilter>,
+
+    #[serde(skip)]
+    pub tags: Option<TagFilter>,
 }
 
 impl NodeCondition {
@@ -0,0 +1,154 @@
+use super::{Deserialize, FromPgRow, Serialize, Table, Updatable, Uuid};
+use ipnet::IpNet;
+use libipam::types::vlan::VlanId;
+use macros::MapQuery;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Table, FromPgRow)]
+#[table_name("tags")]
+#[order_by("name")]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+
+    /// Colour as `#rrggbb`
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, Updatable)]
+pub struct UpdateTag {
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, MapQuery)]
+pub struct TagCondition {
+    pub id: Option<Uuid>,
+    pub name: Option<String>,
+}
+
+impl TagCondition {
+    pub fn p_key(id: Uuid) -> Self {
+        Self {
+            id: Some(id),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Table, FromPgRow)]
+#[table_name("network_tags")]
+pub struct NetworkTag {
+    pub network_id: Uuid,
+    pub tag_id: Uuid,
+}
+
+#[derive(Debug, Default, MapQuery)]
+pub struct NetworkTagCondition {
+    pub network_id: Option<Uuid>,
+    pub tag_id: Option<Uuid>,
+}
+
+/// Only the stored addresses can be tagged
+#[derive(Serialize, Debug, Clone, Table, FromPgRow)]
+#[table_name("address_tags")]
+pub struct AddressTag {
+    pub ip: IpNet,
+    pub network_id: Uuid,
+    pub tag_id: Uuid,
+}
+
+#[derive(Debug, Default, MapQuery)]
+pub struct AddressTagCondition {
+    pub ip: Option<IpNet>,
+    pub network_id: Option<Uuid>,
+    pub tag_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Debug, Clone, Table, FromPgRow)]
+#[table_name("node_tags")]
+pub struct NodeTag {
+    pub node_id: Uuid,
+    pub tag_id: Uuid,
+}
+
+#[derive(Debug, Default, MapQuery)]
+pub struct NodeTagCondition {
+    pub node_id: Option<Uuid>,
+    pub tag_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Debug, Clone, Table, FromPgRow)]
+#[table_name("vlan_tags")]
+pub struct VlanTag {
+    pub vlan_id: VlanId,
+    pub tag_id: Uuid,
+}
+
+#[derive(Debug, Default, MapQuery)]
+pub struct VlanTagCondition {
+    pub vlan_id: Option<VlanId>,
+    pub tag_id: Option<Uuid>,
+}
+
+/// Join table between the tags and the objects of a table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TagJoin {
+    pub table: &'static str,
+
+    /// Primary key of the object
+    pub column: &'static str,
+
+    /// Columns of the join table that reference the primary key
+    pub key: &'static str,
+}
+
+impl TagJoin {
+    pub const NETWORK: Self = Self {
+        table: "network_tags",
+        column: "id",
+        key: "network_id",
+    };
+
+    pub const ADDRESSES: Self = Self {
+        table: "address_tags",
+        column: "(ip, network_id)",
+        key: "ip, network_id",
+    };
+
+    pub const NODE: Self = Self {
+        table: "node_tags",
+        column: "id",
+        key: "node_id",
+    };
+
+    pub const VLAN: Self = Self {
+        table: "vlan_tags",
+        column: "id",
+        key: "vlan_id",
+    };
+}
+
+/// Objects with any, or all, of the tags
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagFilter {
+    pub names: Vec<String>,
+    pub all: bool,
+    pub join: TagJoin,
+}
+
+impl TagFilter {
+    /// Condition of the primary key, the tag names are the parameter `pos`
+    pub fn predicate(&self, pos: usize) -> String {
+        let TagJoin { table, column, key } = self.join;
+
+        let having = if self.all {
+            format!(" GROUP BY {key} HAVING count(DISTINCT tags.id) = cardinality(${pos})")
+        } else {
+            String::new()
+        };
+
+        format!(
+            " {column} IN (SELECT {key} FROM {table} JOIN tags ON tags.id = {table}.tag_id WHERE tags.name = ANY(${pos}){having})"
+        )
+    }
+}
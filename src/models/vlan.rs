@@ -1,4 +1,8 @@
-use super::{FromPgRow, Table, Updatable};
+use super::{
+    FromPgRow, Table, Updatable,
+    custom_field::{CustomFields, CustomFieldsFilter},
+    tag::TagFilter,
+};
 use libipam::types::vlan::VlanId;
 use macros::MapQuery;
 use serde::{Deserialize, Serialize};
@@ -8,25 +12,35 @@ use serde::{Deserialize, Serialize};
 pub struct Vlan {
     pub id: VlanId,
     pub description: Option<String>,
+
+    #[serde(default)]
+    pub custom_fields: CustomFields,
 }
 
 #[derive(Deserialize, Debug, Default, Updatable)]
 pub struct UpdateVlan {
     pub id: Option<VlanId>,
     pub description: Option<String>,
+    pub custom_fields: Option<CustomFields>,
 }
 
-#[derive(Debug, Default, MapQuery)]
+#[derive(Debug, Default, MapQuery, Deserialize)]
 pub struct VlanCondition {
     pub id: Option<VlanId>,
     pub description: Option<String>,
+
+    #[serde(flatten, deserialize_with = "CustomFieldsFilter::from_query")]
+    pub custom_fields: Option<CustomFieldsFilter>,
+
+    #[serde(skip)]
+    pub tags: Option<TagFilter>,
 }
 
 impl VlanCondition {
     pub fn p_key(id: VlanId) -> Self {
         Self {
             id: Some(id),
-            description: None,
+            ..Default::default()
         }
     }
 }
@@ -0,0 +1,38 @@
+use super::{Deserialize, FromPgRow, Serialize, Table, Updatable, Uuid};
+use macros::MapQuery;
+
+/// Routing domain, the networks of different vrfs can overlap
+#[derive(Deserialize, Serialize, Debug, Clone, Table, FromPgRow)]
+#[table_name("vrfs")]
+#[order_by("name")]
+pub struct Vrf {
+    pub id: Uuid,
+    pub name: String,
+
+    /// Route distinguisher, as 65000:1
+    pub rd: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, Updatable)]
+pub struct UpdateVrf {
+    pub name: Option<String>,
+    pub rd: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, MapQuery)]
+pub struct VrfCondition {
+    pub id: Option<Uuid>,
+    pub name: Option<String>,
+    pub rd: Option<String>,
+}
+
+impl VrfCondition {
+    pub fn p_key(id: Uuid) -> Self {
+        Self {
+            id: Some(id),
+            ..Default::default()
+        }
+    }
+}
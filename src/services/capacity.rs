@@ -0,0 +1,95 @@
+use crate::{
+    app_state::StateType,
+    database::{
+        repository::{Repository, error::RepositoryError},
+        transaction::Transaction as _,
+    },
+    models::{
+        capacity::{CapacityAlert, UpdateCapacity},
+        network::{NetwCondition, Network},
+    },
+};
+use libipam::services::capacity::utilisation;
+use std::time::Duration;
+
+/// Evaluates the thresholds of the networks every `period`
+pub async fn evaluator(state: StateType, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        match evaluate(&state).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("{n} networks changed of capacity level"),
+            Err(e) => tracing::error!("capacity evaluation: {e}"),
+        }
+    }
+}
+
+/// Records an alert for each network whose level isn't the level of the last evaluation,
+/// returns the number of alerts
+pub async fn evaluate(state: &StateType) -> Result<usize, RepositoryError> {
+    let networks = match state
+        .get::<Network>(NetwCondition::default(), None, None)
+        .await
+    {
+        Ok(e) => e,
+        Err(RepositoryError::RowNotFound) => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let alerts = networks
+        .iter()
+        .filter_map(|network| {
+            let utilisation = utilisation(network.used, network.free);
+            let level = network.thresholds(state.thresholds()).level(utilisation);
+
+            (level != network.capacity).then(|| CapacityAlert::new(network, level, utilisation))
+        })
+        .collect::<Vec<_>>();
+
+    if alerts.is_empty() {
+        return Ok(0);
+    }
+
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        for alert in &alerts {
+            transaction.insert(alert.clone()).await?;
+
+            transaction
+                .update::<Network, _, _>(
+                    UpdateCapacity {
+                        capacity: alert.level,
+                    },
+                    NetwCondition::p_key(alert.network_id),
+                )
+                .await?;
+        }
+
+        Ok::<_, RepositoryError>(())
+    }
+    .await;
+
+    if let Err(e) = resp {
+        transaction.rollback().await?;
+        return Err(e);
+    }
+
+    transaction.commit().await?;
+
+    for alert in &alerts {
+        if alert.level > alert.previous {
+            tracing::warn!(
+                "The network {} is at {:.2}% of its hosts, level {:?}",
+                alert.network_id,
+                alert.utilisation,
+                alert.level
+            );
+        }
+    }
+
+    Ok(alerts.len())
+}
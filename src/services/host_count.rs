@@ -0,0 +1,95 @@
+use crate::{
+    app_state::StateType,
+    database::{repository::error::RepositoryError, transaction::Transaction as _},
+    models::network::{
+        NetwCondition, Network,
+        addresses::{AddrCondition, Addresses, StatusAddr},
+        host_count::{HostCountDrift, recount},
+    },
+};
+use std::{collections::HashMap, time::Duration};
+
+/// Checks the counters of the networks every `period`, the drift is only fixed with `fix`
+pub async fn checker(state: StateType, period: Duration, fix: bool) {
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        let _permit = state.heavy_task().acquire().await;
+
+        match check(&state, fix).await {
+            Ok(drift) => {
+                for x in &drift {
+                    tracing::warn!(
+                        "host count drift of {}: stored {:?}, expected {:?}",
+                        x.subnet,
+                        x.stored,
+                        x.expected
+                    );
+                }
+            }
+            Err(e) => tracing::error!("host count check: {e}"),
+        }
+    }
+}
+
+/// Counts the counters of the networks from the networks and addresses stored, and returns the
+/// networks with other counters. With `fix` the counters are written in one transaction
+pub async fn check(state: &StateType, fix: bool) -> Result<Vec<HostCountDrift>, RepositoryError> {
+    let mut transaction = state.transaction().await?;
+
+    let resp = async {
+        // The counters aren't changed by other requests between the count and the fix
+        if fix {
+            transaction.lock::<Network>().await?;
+        }
+
+        let networks = transaction
+            .get::<Network>(NetwCondition::default(), None, None)
+            .await?;
+
+        let mut used = HashMap::<_, u128>::new();
+
+        for status in StatusAddr::USED {
+            for addr in transaction
+                .get::<Addresses>(
+                    AddrCondition {
+                        status: Some(status),
+                        ..Default::default()
+                    },
+                    None,
+                    None,
+                )
+                .await?
+            {
+                *used.entry(addr.network_id).or_default() += 1;
+            }
+        }
+
+        let drift = recount(&networks, &used);
+
+        if fix {
+            for x in &drift {
+                transaction
+                    .update::<Network, _, _>(x.expected, NetwCondition::p_key(x.network_id))
+                    .await?;
+            }
+        }
+
+        Ok::<_, RepositoryError>(drift)
+    }
+    .await;
+
+    let drift = match resp {
+        Ok(e) => e,
+        Err(e) => {
+            transaction.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    transaction.commit().await?;
+
+    Ok(drift)
+}
@@ -1,3 +1,6 @@
+pub mod capacity;
+pub mod host_count;
+
 use crate::{
     database::repository::{Repository, error::RepositoryError},
     models::user::{Role, User, UserCondition},
@@ -0,0 +1,226 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, collections::BinaryHeap, ops::Range};
+
+/// How the free addresses of a network are picked
+///
+/// - `FirstFit`: the lowest free addresses
+/// - `LastFit`: the highest free addresses
+/// - `Random`: any free address, so the neighbours aren't predictable
+/// - `Sparse`: the middle of the largest free block, so the addresses are spread over the range
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "ALLOCATION_STRATEGY")]
+pub enum Strategy {
+    #[default]
+    FirstFit,
+    LastFit,
+    Random,
+    Sparse,
+}
+
+impl Strategy {
+    /// `n` positions of `positions` that aren't in `taken`, fewer if they're exhausted
+    #[must_use]
+    pub fn pick(self, mut taken: Vec<u128>, n: usize, positions: Range<u128>) -> Vec<u128> {
+        if positions.is_empty() {
+            return Vec::new();
+        }
+
+        taken.retain(|x| positions.contains(x));
+        taken.sort_unstable();
+        taken.dedup();
+
+        match self {
+            Self::FirstFit => first_fit(&taken, n, positions),
+            Self::LastFit => last_fit(&taken, n, positions),
+            Self::Random => random(taken, n, positions),
+            Self::Sparse => sparse(&taken, n, positions),
+        }
+    }
+}
+
+fn first_fit(taken: &[u128], n: usize, positions: Range<u128>) -> Vec<u128> {
+    let mut taken = taken.iter().peekable();
+    let mut resp = Vec::new();
+    let mut index = positions.start;
+
+    while resp.len() < n && index < positions.end {
+        if taken.next_if_eq(&&index).is_none() {
+            resp.push(index);
+        }
+        index += 1;
+    }
+
+    resp
+}
+
+fn last_fit(taken: &[u128], n: usize, positions: Range<u128>) -> Vec<u128> {
+    let mut taken = taken.iter().rev().peekable();
+    let mut resp = Vec::new();
+    let mut index = positions.end;
+
+    while resp.len() < n && index > positions.start {
+        index -= 1;
+
+        if taken.next_if_eq(&&index).is_none() {
+            resp.push(index);
+        }
+    }
+
+    resp
+}
+
+/// Picks the k-th free position with a random k, `taken` is kept sorted with the picked positions
+fn random(mut taken: Vec<u128>, n: usize, positions: Range<u128>) -> Vec<u128> {
+    let mut rng = rand::rng();
+    let mut resp = Vec::new();
+
+    while resp.len() < n {
+        let free = (positions.end - positions.start) - taken.len() as u128;
+
+        if free == 0 {
+            break;
+        }
+
+        let mut index = positions.start + rng.random_range(0..free);
+
+        for x in &taken {
+            if *x > index {
+                break;
+            }
+            index += 1;
+        }
+
+        let pos = taken.partition_point(|x| *x < index);
+        taken.insert(pos, index);
+        resp.push(index);
+    }
+
+    resp
+}
+
+/// Picks the middle of the largest free block, the lowest block wins a tie
+fn sparse(taken: &[u128], n: usize, positions: Range<u128>) -> Vec<u128> {
+    let mut blocks = BinaryHeap::new();
+    let mut start = positions.start;
+
+    for x in taken.iter().copied().chain(std::iter::once(positions.end)) {
+        if x > start {
+            blocks.push((x - start, Reverse(start)));
+        }
+        start = x + 1;
+    }
+
+    let mut resp = Vec::new();
+
+    while resp.len() < n {
+        let Some((len, Reverse(start))) = blocks.pop() else {
+            break;
+        };
+
+        let middle = start + len / 2;
+        resp.push(middle);
+
+        if middle > start {
+            blocks.push((middle - start, Reverse(start)));
+        }
+
+        if start + len > middle + 1 {
+            blocks.push((start + len - middle - 1, Reverse(middle + 1)));
+        }
+    }
+
+    resp
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const STRATEGIES: [Strategy; 4] = [
+        Strategy::FirstFit,
+        Strategy::LastFit,
+        Strategy::Random,
+        Strategy::Sparse,
+    ];
+
+    #[test]
+    fn first_fit_skips_taken() {
+        assert_eq!(
+            Strategy::FirstFit.pick(vec![0, 2, 3], 3, 0..10),
+            vec![1, 4, 5]
+        );
+    }
+
+    #[test]
+    fn last_fit_from_the_top() {
+        assert_eq!(Strategy::LastFit.pick(vec![9, 7], 3, 0..10), vec![8, 6, 5]);
+    }
+
+    #[test]
+    fn sparse_spreads_addresses() {
+        assert_eq!(Strategy::Sparse.pick(vec![], 3, 0..8), vec![4, 2, 6]);
+        assert_eq!(Strategy::Sparse.pick(vec![4], 1, 0..8), vec![2]);
+        assert_eq!(Strategy::Sparse.pick(vec![0, 1, 2], 1, 0..10), vec![6]);
+    }
+
+    #[test]
+    fn random_picks_free_positions() {
+        let taken = vec![10, 11, 15, 40];
+
+        for _ in 0..100 {
+            let mut resp = Strategy::Random.pick(taken.clone(), 5, 10..50);
+
+            assert_eq!(resp.len(), 5);
+            assert!(
+                resp.iter()
+                    .all(|x| (10..50).contains(x) && !taken.contains(x))
+            );
+
+            resp.sort_unstable();
+            resp.dedup();
+            assert_eq!(resp.len(), 5);
+        }
+    }
+
+    #[test]
+    fn strategies_inside_positions() {
+        for strategy in STRATEGIES {
+            let resp = strategy.pick(vec![0, 1, 99], 10, 20..30);
+
+            assert_eq!(resp.len(), 10, "{strategy:?}");
+            assert!(resp.iter().all(|x| (20..30).contains(x)), "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn strategies_exhaustion() {
+        for strategy in STRATEGIES {
+            let mut resp = strategy.pick(vec![1, 3], 10, 0..6);
+            resp.sort_unstable();
+
+            assert_eq!(resp, vec![0, 2, 4, 5], "{strategy:?}");
+            assert!(
+                strategy.pick(vec![0, 1, 2], 1, 0..3).is_empty(),
+                "{strategy:?}"
+            );
+            assert!(strategy.pick(vec![], 1, 5..5).is_empty(), "{strategy:?}");
+            assert!(
+                strategy
+                    .pick(vec![], 1, Range { start: 6, end: 5 })
+                    .is_empty(),
+                "{strategy:?}"
+            );
+            assert!(strategy.pick(vec![], 0, 0..5).is_empty(), "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn strategies_large_range() {
+        let end = u128::from(u64::MAX);
+
+        assert_eq!(Strategy::LastFit.pick(vec![], 1, 0..end), vec![end - 1]);
+        assert_eq!(Strategy::Sparse.pick(vec![], 1, 0..end), vec![end / 2]);
+        assert_eq!(Strategy::Random.pick(vec![], 3, 0..end).len(), 3);
+    }
+}
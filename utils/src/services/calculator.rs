@@ -0,0 +1,192 @@
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+use serde::Serialize;
+
+use super::ipam::{ip_to_u128, range_to_subnets, u128_to_ip};
+use crate::types::host_count::HostCount;
+
+#[derive(Debug)]
+pub struct CalculatorError(String);
+
+/// Values of a network, the usable hosts are the same hosts counted by `HostCount`
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NetworkInfo {
+    pub network: IpNet,
+    pub netmask: IpAddr,
+    pub wildcard: IpAddr,
+
+    /// The ipv6 networks don't have broadcast
+    pub broadcast: Option<IpAddr>,
+    pub first_usable: IpAddr,
+    pub last_usable: IpAddr,
+    pub hosts: HostCount,
+}
+
+#[must_use]
+pub fn info(network: IpNet) -> NetworkInfo {
+    let network = network.trunc();
+    let ipv4 = network.network().is_ipv4();
+    let hosts = HostCount::from(network);
+
+    let start = ip_to_u128(network.network());
+    let end = ip_to_u128(network.broadcast());
+    let first = if end - start >= hosts.as_u128() {
+        start + 1
+    } else {
+        start
+    };
+
+    NetworkInfo {
+        network,
+        netmask: network.netmask(),
+        wildcard: network.hostmask(),
+        broadcast: ipv4.then(|| network.broadcast()),
+        first_usable: u128_to_ip(first, ipv4),
+        last_usable: u128_to_ip(first + hosts.as_u128().saturating_sub(1), ipv4),
+        hosts,
+    }
+}
+
+/// Minimal list of networks that cover exactly the addresses between `start` and `end`
+///
+/// # Errors
+///
+/// Will return `Err` if the addresses aren't of the same family or `start` is greater than `end`
+pub fn range_to_networks(start: IpAddr, end: IpAddr) -> Result<Vec<IpNet>, CalculatorError> {
+    if start.is_ipv4() != end.is_ipv4() {
+        return Err(CalculatorError(format!(
+            "The addresses {start} and {end} aren't of the same family"
+        )));
+    }
+
+    if start > end {
+        return Err(CalculatorError(format!(
+            "The address {start} is greater than {end}"
+        )));
+    }
+
+    Ok(range_to_subnets(
+        ip_to_u128(start),
+        ip_to_u128(end),
+        start.is_ipv4(),
+    ))
+}
+
+/// Minimal list of networks that cover the same addresses as `networks`
+#[must_use]
+pub fn summarize(networks: &[IpNet]) -> Vec<IpNet> {
+    IpNet::aggregate(&networks.to_vec())
+}
+
+impl std::fmt::Display for CalculatorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Calculator error: {}", self.0)
+    }
+}
+
+impl std::error::Error for CalculatorError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn info_ipv4() {
+        let resp = info("172.16.5.10/22".parse().unwrap());
+
+        assert_eq!(resp.network, "172.16.4.0/22".parse::<IpNet>().unwrap());
+        assert_eq!(resp.netmask, "255.255.252.0".parse::<IpAddr>().unwrap());
+        assert_eq!(resp.wildcard, "0.0.3.255".parse::<IpAddr>().unwrap());
+        assert_eq!(resp.broadcast, Some("172.16.7.255".parse().unwrap()));
+        assert_eq!(resp.first_usable, "172.16.4.1".parse::<IpAddr>().unwrap());
+        assert_eq!(resp.last_usable, "172.16.7.254".parse::<IpAddr>().unwrap());
+        assert_eq!(resp.hosts, 1022);
+    }
+
+    #[test]
+    fn info_ipv4_point_to_point() {
+        let resp = info("10.0.0.0/31".parse().unwrap());
+
+        assert_eq!(resp.first_usable, "10.0.0.0".parse::<IpAddr>().unwrap());
+        assert_eq!(resp.last_usable, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(resp.hosts, 2);
+
+        let resp = info("10.0.0.1/32".parse().unwrap());
+
+        assert_eq!(resp.first_usable, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(resp.last_usable, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(resp.hosts, 1);
+    }
+
+    #[test]
+    fn info_ipv6() {
+        let resp = info("2001:db8::/64".parse().unwrap());
+
+        assert_eq!(resp.broadcast, None);
+        assert_eq!(resp.wildcard, "::ffff:ffff:ffff:ffff".parse::<IpAddr>().unwrap());
+        assert_eq!(resp.first_usable, "2001:db8::1".parse::<IpAddr>().unwrap());
+        assert_eq!(
+            resp.last_usable,
+            "2001:db8::ffff:ffff:ffff:fffe".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(resp.hosts, (1u128 << 64) - 2);
+
+        let resp = info("::/0".parse().unwrap());
+        assert_eq!(resp.first_usable, "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(resp.hosts, u128::MAX - 1);
+    }
+
+    #[test]
+    fn range_ipv4() {
+        let resp = range_to_networks(
+            "192.168.0.10".parse().unwrap(),
+            "192.168.0.20".parse().unwrap(),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            resp,
+            vec![
+                "192.168.0.10/31",
+                "192.168.0.12/30",
+                "192.168.0.16/30",
+                "192.168.0.20/32"
+            ]
+        );
+    }
+
+    #[test]
+    fn range_ipv6_and_errors() {
+        let resp = range_to_networks("::".parse().unwrap(), "ffff:ffff::".parse().unwrap());
+        assert_eq!(resp.unwrap().len(), 33);
+
+        assert!(
+            range_to_networks("10.0.0.1".parse().unwrap(), "10.0.0.0".parse().unwrap()).is_err()
+        );
+        assert!(range_to_networks("10.0.0.1".parse().unwrap(), "::1".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn summarize_networks() {
+        let networks = [
+            "10.0.0.0/24".parse().unwrap(),
+            "10.0.1.0/24".parse().unwrap(),
+            "10.0.2.0/23".parse().unwrap(),
+            "10.0.2.128/25".parse().unwrap(),
+            "2001:db8::/33".parse().unwrap(),
+            "2001:db8:8000::/33".parse().unwrap(),
+        ];
+
+        assert_eq!(
+            summarize(&networks),
+            vec![
+                "10.0.0.0/22".parse::<IpNet>().unwrap(),
+                "2001:db8::/32".parse::<IpNet>().unwrap(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::host_count::HostCount;
+
+/// Utilisation of a network compared with its thresholds, ordered from the lowest
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize, sqlx::Type,
+)]
+#[sqlx(type_name = "CAPACITY_LEVEL")]
+pub enum Level {
+    #[default]
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// Percentages of used hosts where a network is in warning and critical level
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Thresholds {
+    warning: u8,
+    critical: u8,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ThresholdsError(String);
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            warning: 80,
+            critical: 95,
+        }
+    }
+}
+
+impl Thresholds {
+    /// # Errors
+    ///
+    /// Will return `Err` if a threshold is greater than 100 or the warning is greater than the critical
+    pub fn new(warning: u8, critical: u8) -> Result<Self, ThresholdsError> {
+        if warning > 100 || critical > 100 {
+            return Err(ThresholdsError(
+                "The thresholds are percentages between 0 and 100".to_string(),
+            ));
+        }
+
+        if warning > critical {
+            return Err(ThresholdsError(format!(
+                "The warning threshold {warning} is greater than the critical threshold {critical}"
+            )));
+        }
+
+        Ok(Self { warning, critical })
+    }
+
+    #[must_use]
+    pub fn warning(&self) -> u8 {
+        self.warning
+    }
+
+    #[must_use]
+    pub fn critical(&self) -> u8 {
+        self.critical
+    }
+
+    /// Thresholds of a network, the missing values are the values of `self`
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the resulting thresholds aren't valid
+    pub fn with(
+        &self,
+        warning: Option<i16>,
+        critical: Option<i16>,
+    ) -> Result<Self, ThresholdsError> {
+        let percentage = |x: i16| {
+            u8::try_from(x).map_err(|_| {
+                ThresholdsError("The thresholds are percentages between 0 and 100".to_string())
+            })
+        };
+
+        Self::new(
+            warning.map_or(Ok(self.warning), percentage)?,
+            critical.map_or(Ok(self.critical), percentage)?,
+        )
+    }
+
+    #[must_use]
+    pub fn level(&self, utilisation: f64) -> Level {
+        if utilisation >= f64::from(self.critical) {
+            Level::Critical
+        } else if utilisation >= f64::from(self.warning) {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+}
+
+/// Percentage of the hosts of a network that are used, a network without hosts is empty
+#[must_use]
+pub fn utilisation(used: HostCount, free: HostCount) -> f64 {
+    let used = used.as_u128() as f64;
+    let total = used + free.as_u128() as f64;
+
+    if total == 0.0 {
+        0.0
+    } else {
+        used / total * 100.0
+    }
+}
+
+impl std::fmt::Display for ThresholdsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ThresholdsError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn thresholds_new() {
+        assert!(Thresholds::new(80, 95).is_ok());
+        assert!(Thresholds::new(90, 90).is_ok());
+        assert!(Thresholds::new(96, 95).is_err());
+        assert!(Thresholds::new(80, 101).is_err());
+    }
+
+    #[test]
+    fn thresholds_with_overrides() {
+        let global = Thresholds::default();
+
+        assert_eq!(global.with(None, None), Ok(global));
+        assert_eq!(global.with(Some(50), None), Thresholds::new(50, 95));
+        assert_eq!(global.with(None, Some(90)), Thresholds::new(80, 90));
+        assert!(global.with(None, Some(70)).is_err());
+        assert!(global.with(Some(-1), None).is_err());
+        assert!(global.with(Some(300), None).is_err());
+    }
+
+    #[test]
+    fn thresholds_level() {
+        let thresholds = Thresholds::new(80, 95).unwrap();
+
+        assert_eq!(thresholds.level(0.0), Level::Normal);
+        assert_eq!(thresholds.level(79.9), Level::Normal);
+        assert_eq!(thresholds.level(80.0), Level::Warning);
+        assert_eq!(thresholds.level(94.9), Level::Warning);
+        assert_eq!(thresholds.level(95.0), Level::Critical);
+        assert_eq!(thresholds.level(100.0), Level::Critical);
+    }
+
+    #[test]
+    fn utilisation_of_network() {
+        assert_eq!(utilisation(HostCount::from(0), HostCount::from(254)), 0.0);
+        assert_eq!(
+            utilisation(HostCount::from(127), HostCount::from(127)),
+            50.0
+        );
+        assert_eq!(utilisation(HostCount::from(254), HostCount::from(0)), 100.0);
+        assert_eq!(utilisation(HostCount::from(0), HostCount::from(0)), 0.0);
+        assert!(utilisation(HostCount::new_max(), HostCount::from(1)) > 99.9);
+    }
+
+    #[test]
+    fn level_order() {
+        assert!(Level::Critical > Level::Warning);
+        assert!(Level::Warning > Level::Normal);
+    }
+}
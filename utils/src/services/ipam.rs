@@ -1,4 +1,5 @@
-use std::net::{IpAddr, Ipv4Addr};
+use std::cmp::Reverse;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use axum::{
     http::{Response, StatusCode},
@@ -6,26 +7,36 @@ use axum::{
 };
 use ipnet::IpNet;
 
+use crate::types::host_count::HostCount;
+
 #[derive(Debug)]
 pub struct SubnettingError(String);
 
+/// Iterator over the subnets of a given prefix inside a network.
+///
+/// The addresses are handled as `u128`, so the same iterator works for ipv4 and ipv6
+/// networks. The subnets are calculated on demand, thus a `/48` split in `/64`
+/// doesn't need to be collected in memory.
 #[derive(Debug)]
 pub struct SubnetList {
-    start: u32,
-    end: u32,
+    start: u128,
+    ipv4: bool,
     prefix: u8,
-    hosts: u32,
-    step: u32,
+    hosts: u128,
+    len: u128,
+    step: u128,
 }
 
 impl SubnetList {
     /// # Errors
     ///
     /// Will return `Err` if:
-    ///     - the prefinx is greater than network's prefix
-    ///     - the parameter `network` is ipv6, as the application cannot create an list of ipv6
+    ///     - the prefix is smaller than or equal to the network's prefix
+    ///     - the prefix is greater than the maximum prefix of the network's family
+    ///     - the number of subnets cannot be represented as `usize`
     pub fn new(network: IpNet, prefix: u8) -> Result<Self, SubnettingError> {
         let network_prefix = network.prefix_len();
+        let max_prefix = network.max_prefix_len();
 
         if prefix <= network_prefix {
             return Err(SubnettingError(format!(
@@ -33,25 +44,26 @@ impl SubnetList {
             )));
         }
 
-        let start = match network.network() {
-            IpAddr::V4(ipv4_net) => u32::from(ipv4_net),
-            IpAddr::V6(_) => {
-                return Err(SubnettingError(
-                    "You cannot create subnet in ipv6".to_string(),
-                ));
-            }
-        };
+        if prefix > max_prefix {
+            return Err(SubnettingError(format!(
+                "The prefix subnet {prefix} is greater than {max_prefix}"
+            )));
+        }
 
-        let subnets = 2u32.pow(u32::from(prefix - network_prefix));
+        let bits = u32::from(prefix - network_prefix);
 
-        let hosts = 2_u32.pow(u32::from(32 - prefix));
+        if bits >= usize::BITS {
+            return Err(SubnettingError(format!(
+                "Too many subnets, 2^{bits} subnets cannot be listed"
+            )));
+        }
 
-        let end = start + (hosts * (subnets));
         Ok(Self {
-            start,
-            end,
+            start: ip_to_u128(network.network()),
+            ipv4: network.network().is_ipv4(),
             prefix,
-            hosts,
+            hosts: 1 << (max_prefix - prefix),
+            len: 1 << bits,
             step: 0,
         })
     }
@@ -61,10 +73,10 @@ impl Iterator for SubnetList {
     type Item = IpNet;
 
     fn next(&mut self) -> Option<Self::Item> {
-        ((self.start + (self.step * self.hosts)) <= self.end)
+        (self.step < self.len)
             .then(|| {
                 let resp = IpNet::new(
-                    IpAddr::V4(Ipv4Addr::from(self.start + (self.hosts * self.step))),
+                    u128_to_ip(self.start + (self.hosts * self.step), self.ipv4),
                     self.prefix,
                 )
                 .ok();
@@ -75,11 +87,237 @@ impl Iterator for SubnetList {
             })
             .flatten()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
 }
 
 impl ExactSizeIterator for SubnetList {
     fn len(&self) -> usize {
-        ((self.end - (self.start + self.step)) / self.hosts) as usize
+        // The constructor guarantees that the number of subnets fits in usize
+        usize::try_from(self.len - self.step).unwrap_or(usize::MAX)
+    }
+}
+
+/// Criteria to choose the free block where a new subnet is created
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+pub enum Fit {
+    /// The first free block, in address order, where the subnet fits
+    #[default]
+    FirstFit,
+
+    /// The smallest free block where the subnet fits, it leaves the bigger blocks unfragmented
+    BestFit,
+}
+
+/// Returns the minimal list of blocks of `network` that aren't covered by any of `used`.
+///
+/// The blocks of `used` that don't belong to `network` are ignored
+#[must_use]
+pub fn free_blocks(network: IpNet, used: &[IpNet]) -> Vec<IpNet> {
+    let network = network.trunc();
+    let ipv4 = network.network().is_ipv4();
+
+    let mut used = used
+        .iter()
+        .filter(|x| network.contains(*x))
+        .map(|x| (ip_to_u128(x.network()), ip_to_u128(x.broadcast())))
+        .collect::<Vec<_>>();
+    used.sort_unstable();
+
+    let mut resp = Vec::new();
+    let mut start = Some(ip_to_u128(network.network()));
+    let end = ip_to_u128(network.broadcast());
+
+    for (used_start, used_end) in used {
+        let Some(free_start) = start else {
+            break;
+        };
+
+        if used_start > free_start {
+            resp.extend(range_to_subnets(free_start, used_start - 1, ipv4));
+        }
+
+        if used_end >= free_start {
+            start = used_end.checked_add(1).filter(|x| *x <= end);
+        }
+    }
+
+    if let Some(free_start) = start {
+        resp.extend(range_to_subnets(free_start, end, ipv4));
+    }
+
+    resp
+}
+
+/// Finds a free subnet with the prefix `prefix` inside `network`, skipping the blocks of `used`
+///
+/// # Errors
+///
+/// Will return `Err` if:
+///     - the prefix isn't greater than the network's prefix or is out of range
+///     - there isn't a free block big enough
+pub fn find_free_subnet(
+    network: IpNet,
+    used: &[IpNet],
+    prefix: u8,
+    fit: Fit,
+) -> Result<IpNet, SubnettingError> {
+    if prefix <= network.prefix_len() || prefix > network.max_prefix_len() {
+        return Err(SubnettingError(format!(
+            "The prefix subnet {prefix} must be between {} and {}",
+            network.prefix_len() + 1,
+            network.max_prefix_len()
+        )));
+    }
+
+    let mut blocks = free_blocks(network, used)
+        .into_iter()
+        .filter(|x| x.prefix_len() <= prefix);
+
+    let block = match fit {
+        Fit::FirstFit => blocks.next(),
+        Fit::BestFit => blocks.rev().max_by_key(IpNet::prefix_len),
+    };
+
+    block
+        .and_then(|x| IpNet::new(x.network(), prefix).ok())
+        .ok_or(SubnettingError(format!(
+            "There isn't a free /{prefix} in {network}"
+        )))
+}
+
+/// Hosts needed by one or more subnets of a VLSM plan
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct HostRequirement {
+    pub name: String,
+    pub hosts: u128,
+
+    /// Number of subnets with the same requirement, as the point to point links
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PlannedSubnet {
+    pub name: String,
+    pub hosts: u128,
+    pub subnet: IpNet,
+}
+
+/// Smallest prefix inside `network` with at least `hosts` hosts, as they are counted by `HostCount`
+#[must_use]
+pub fn prefix_for_hosts(network: IpNet, hosts: u128) -> Option<u8> {
+    ((network.prefix_len() + 1)..=network.max_prefix_len())
+        .rev()
+        .find(|prefix| {
+            HostCount::new(network.max_prefix_len(), *prefix).is_some_and(|x| x.as_u128() >= hosts)
+        })
+}
+
+/// Variable length subnet plan of `network`.
+///
+/// The requirements are placed from the biggest to the smallest one in the first free block,
+/// the blocks in `used` are never assigned
+///
+/// # Errors
+///
+/// Will return `Err` if one requirement doesn't have hosts or there isn't space for all of them
+pub fn vlsm(
+    network: IpNet,
+    used: &[IpNet],
+    requirements: Vec<HostRequirement>,
+) -> Result<Vec<PlannedSubnet>, SubnettingError> {
+    let mut requirements = requirements
+        .into_iter()
+        .flat_map(|HostRequirement { name, hosts, count }| {
+            let count = count.unwrap_or(1);
+            (1..=count).map(move |i| {
+                let name = if count > 1 {
+                    format!("{name} {i}")
+                } else {
+                    name.clone()
+                };
+                (name, hosts)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    requirements.sort_by_key(|x| Reverse(x.1));
+
+    let mut used = used.to_vec();
+    let mut resp = Vec::with_capacity(requirements.len());
+
+    for (name, hosts) in requirements {
+        let prefix = (hosts > 0)
+            .then(|| prefix_for_hosts(network, hosts))
+            .flatten()
+            .ok_or(SubnettingError(format!(
+                "{name} requires {hosts} hosts, that doesn't fit in {network}"
+            )))?;
+
+        let subnet = find_free_subnet(network, &used, prefix, Fit::FirstFit)
+            .map_err(|e| SubnettingError(format!("{name}: {}", e.0)))?;
+
+        used.push(subnet);
+        resp.push(PlannedSubnet {
+            name,
+            hosts,
+            subnet,
+        });
+    }
+
+    Ok(resp)
+}
+
+/// Minimal list of subnets that cover exactly the addresses between `start` and `end`
+pub(crate) fn range_to_subnets(mut start: u128, end: u128, ipv4: bool) -> Vec<IpNet> {
+    let max_bits = if ipv4 { 32 } else { 128 };
+    let mut resp = Vec::new();
+
+    loop {
+        let align = if start == 0 {
+            max_bits
+        } else {
+            start.trailing_zeros().min(max_bits)
+        };
+
+        let size = match (end - start).checked_add(1) {
+            Some(x) => 127 - x.leading_zeros(),
+            None => 128,
+        };
+
+        let host_bits = align.min(size);
+
+        resp.push(IpNet::new(u128_to_ip(start, ipv4), (max_bits - host_bits) as u8).unwrap());
+
+        let last = start + if host_bits == 0 { 0 } else { u128::MAX >> (128 - host_bits) };
+
+        if last >= end {
+            break resp;
+        }
+
+        start = last + 1;
+    }
+}
+
+/// Returns the ip address as a number, the ipv4 addresses use only the lower 32 bits
+#[must_use]
+pub fn ip_to_u128(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(ip) => u128::from(u32::from(ip)),
+        IpAddr::V6(ip) => u128::from(ip),
+    }
+}
+
+/// Builds an ip address from a number, if `ipv4` is true only the lower 32 bits are used
+#[must_use]
+pub fn u128_to_ip(ip: u128, ipv4: bool) -> IpAddr {
+    if ipv4 {
+        IpAddr::V4(Ipv4Addr::from(ip as u32))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(ip))
     }
 }
 
@@ -203,6 +441,210 @@ mod test {
         assert!(subnet.len() == 16);
     }
 
+    #[test]
+    fn sub_net_ipv6_prefix_fifty_six() {
+        let ip = "2001:db8:abcd::/48".parse::<IpNet>().unwrap();
+        let subnet = SubnetList::new(ip, 56).unwrap();
+        assert_eq!(subnet.len(), 256);
+
+        let subnet = subnet.collect::<Vec<IpNet>>();
+        assert_eq!(subnet.len(), 256);
+        assert_eq!(subnet[0], "2001:db8:abcd::/56".parse::<IpNet>().unwrap());
+        assert_eq!(subnet[1], "2001:db8:abcd:100::/56".parse::<IpNet>().unwrap());
+        assert_eq!(
+            subnet[255],
+            "2001:db8:abcd:ff00::/56".parse::<IpNet>().unwrap()
+        );
+    }
+
+    #[test]
+    fn sub_net_ipv6_prefix_sixty_four() {
+        let ip = "2001:db8:abcd::/48".parse::<IpNet>().unwrap();
+        let mut subnet = SubnetList::new(ip, 64).unwrap();
+        assert_eq!(subnet.len(), 65_536);
+        assert_eq!(
+            subnet.next(),
+            Some("2001:db8:abcd::/64".parse::<IpNet>().unwrap())
+        );
+        assert_eq!(subnet.len(), 65_535);
+        assert_eq!(
+            subnet.last(),
+            Some("2001:db8:abcd:ffff::/64".parse::<IpNet>().unwrap())
+        );
+    }
+
+    #[test]
+    fn sub_net_prefix_out_of_range() {
+        let ip = "192.168.0.0/24".parse::<IpNet>().unwrap();
+        assert!(SubnetList::new(ip, 33).is_err());
+        assert!(SubnetList::new(ip, 24).is_err());
+    }
+
+    #[test]
+    fn sub_net_ipv6_too_many_subnets() {
+        let ip = "2001:db8::/32".parse::<IpNet>().unwrap();
+        assert!(SubnetList::new(ip, 128).is_err());
+    }
+
+    #[test]
+    fn free_blocks_ipv4() {
+        let ip = "192.168.0.0/24".parse::<IpNet>().unwrap();
+        let used = [
+            "192.168.0.64/26".parse::<IpNet>().unwrap(),
+            "192.168.0.0/28".parse::<IpNet>().unwrap(),
+            "192.168.0.200/29".parse::<IpNet>().unwrap(),
+            "10.0.0.0/24".parse::<IpNet>().unwrap(),
+        ];
+
+        let resp = free_blocks(ip, &used)
+            .into_iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            resp,
+            vec![
+                "192.168.0.16/28",
+                "192.168.0.32/27",
+                "192.168.0.128/26",
+                "192.168.0.192/29",
+                "192.168.0.208/28",
+                "192.168.0.224/27",
+            ]
+        );
+
+        assert_eq!(free_blocks(ip, &[]), vec![ip]);
+        assert!(free_blocks(ip, &[ip]).is_empty());
+    }
+
+    #[test]
+    fn free_blocks_ipv6() {
+        let ip = "2001:db8::/32".parse::<IpNet>().unwrap();
+        let used = [
+            "2001:db8::/34".parse::<IpNet>().unwrap(),
+            "2001:db8:8000::/33".parse::<IpNet>().unwrap(),
+        ];
+
+        assert_eq!(
+            free_blocks(ip, &used),
+            vec!["2001:db8:4000::/34".parse::<IpNet>().unwrap()]
+        );
+
+        let ip = "::/0".parse::<IpNet>().unwrap();
+        let used = ["::/1".parse::<IpNet>().unwrap()];
+
+        assert_eq!(
+            free_blocks(ip, &used),
+            vec!["8000::/1".parse::<IpNet>().unwrap()]
+        );
+    }
+
+    #[test]
+    fn free_subnet_first_fit() {
+        let ip = "192.168.0.0/24".parse::<IpNet>().unwrap();
+        let used = ["192.168.0.0/26".parse::<IpNet>().unwrap()];
+
+        let resp = find_free_subnet(ip, &used, 26, Fit::FirstFit).unwrap();
+        assert_eq!(resp, "192.168.0.64/26".parse::<IpNet>().unwrap());
+    }
+
+    #[test]
+    fn free_subnet_best_fit() {
+        let ip = "192.168.0.0/24".parse::<IpNet>().unwrap();
+        let used = [
+            "192.168.0.0/28".parse::<IpNet>().unwrap(),
+            "192.168.0.32/27".parse::<IpNet>().unwrap(),
+        ];
+
+        let resp = find_free_subnet(ip, &used, 28, Fit::FirstFit).unwrap();
+        assert_eq!(resp, "192.168.0.16/28".parse::<IpNet>().unwrap());
+
+        let resp = find_free_subnet(ip, &used, 27, Fit::FirstFit).unwrap();
+        assert_eq!(resp, "192.168.0.64/27".parse::<IpNet>().unwrap());
+
+        let used = [
+            "192.168.0.0/26".parse::<IpNet>().unwrap(),
+            "192.168.0.192/28".parse::<IpNet>().unwrap(),
+        ];
+
+        let resp = find_free_subnet(ip, &used, 28, Fit::BestFit).unwrap();
+        assert_eq!(resp, "192.168.0.208/28".parse::<IpNet>().unwrap());
+    }
+
+    #[test]
+    fn free_subnet_exhausted() {
+        let ip = "192.168.0.0/24".parse::<IpNet>().unwrap();
+        let used = [
+            "192.168.0.0/25".parse::<IpNet>().unwrap(),
+            "192.168.0.128/26".parse::<IpNet>().unwrap(),
+            "192.168.0.224/27".parse::<IpNet>().unwrap(),
+        ];
+
+        assert!(find_free_subnet(ip, &used, 26, Fit::BestFit).is_err());
+        assert!(find_free_subnet(ip, &used, 24, Fit::FirstFit).is_err());
+
+        let resp = find_free_subnet(ip, &used, 27, Fit::FirstFit).unwrap();
+        assert_eq!(resp, "192.168.0.192/27".parse::<IpNet>().unwrap());
+    }
+
+    #[test]
+    fn vlsm_by_hosts() {
+        let ip = "192.168.0.0/24".parse::<IpNet>().unwrap();
+        let requirements = vec![
+            HostRequirement {
+                name: "mgmt".to_string(),
+                hosts: 10,
+                count: None,
+            },
+            HostRequirement {
+                name: "sales".to_string(),
+                hosts: 120,
+                count: None,
+            },
+            HostRequirement {
+                name: "p2p".to_string(),
+                hosts: 2,
+                count: Some(4),
+            },
+            HostRequirement {
+                name: "voip".to_string(),
+                hosts: 50,
+                count: None,
+            },
+        ];
+
+        let plan = vlsm(ip, &[], requirements)
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.name, x.subnet.to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(plan[0], ("sales".to_string(), "192.168.0.0/25".to_string()));
+        assert_eq!(plan[1], ("voip".to_string(), "192.168.0.128/26".to_string()));
+        assert_eq!(plan[2], ("mgmt".to_string(), "192.168.0.192/28".to_string()));
+        assert_eq!(plan[3], ("p2p 1".to_string(), "192.168.0.208/31".to_string()));
+        assert_eq!(plan[6], ("p2p 4".to_string(), "192.168.0.214/31".to_string()));
+        assert_eq!(plan.len(), 7);
+    }
+
+    #[test]
+    fn vlsm_skip_used_and_overflow() {
+        let ip = "10.0.0.0/24".parse::<IpNet>().unwrap();
+        let used = ["10.0.0.0/25".parse::<IpNet>().unwrap()];
+        let requirement = |hosts| HostRequirement {
+            name: "net".to_string(),
+            hosts,
+            count: None,
+        };
+
+        let plan = vlsm(ip, &used, vec![requirement(100)]).unwrap();
+        assert_eq!(plan[0].subnet, "10.0.0.128/25".parse::<IpNet>().unwrap());
+
+        assert!(vlsm(ip, &used, vec![requirement(127)]).is_err());
+        assert!(vlsm(ip, &used, vec![requirement(0)]).is_err());
+        assert!(vlsm(ip, &used, vec![requirement(100), requirement(2)]).is_err());
+    }
+
     #[test]
     fn ping_test_pong() {
         let resp = RUNTIME.block_on(async { ping("192.168.0.1".parse().unwrap(), 100).await });
@@ -1,2 +1,6 @@
+pub mod allocation;
 pub mod authentication;
+pub mod calculator;
+pub mod capacity;
 pub mod ipam;
+pub mod reservation;
@@ -0,0 +1,129 @@
+use serde::Serialize;
+
+/// Number of hosts reserved at the start and at the end of a network
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct ReservePolicy {
+    pub first: u16,
+    pub last: u16,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ReservePolicyError(String);
+
+impl ReservePolicy {
+    #[must_use]
+    pub fn new(first: u16, last: u16) -> Self {
+        Self { first, last }
+    }
+
+    /// Policy of a network, the missing values are the values of `self`
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a value is negative
+    pub fn with(&self, first: Option<i16>, last: Option<i16>) -> Result<Self, ReservePolicyError> {
+        let count = |x: i16| {
+            u16::try_from(x).map_err(|_| {
+                ReservePolicyError("The reserved hosts cannot be negative".to_string())
+            })
+        };
+
+        Ok(Self {
+            first: first.map_or(Ok(self.first), count)?,
+            last: last.map_or(Ok(self.last), count)?,
+        })
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.first == 0 && self.last == 0
+    }
+
+    /// Positions of the reserved hosts of a network with `hosts` usable hosts, with their labels.
+    ///
+    /// The first host is the gateway and the next ones are for VRRP, the last host is for
+    /// monitoring. A host is reserved only once if both ends overlap
+    #[must_use]
+    pub fn positions(&self, hosts: u128) -> Vec<(u128, String)> {
+        let first = u128::from(self.first).min(hosts);
+        let last = u128::from(self.last).min(hosts - first);
+
+        let start = (0..first).map(|x| {
+            let label = if x == 0 {
+                "gateway".to_string()
+            } else {
+                format!("vrrp-{x}")
+            };
+
+            (x, label)
+        });
+
+        let end = (0..last).rev().map(|x| {
+            let label = if x == 0 {
+                "monitoring".to_string()
+            } else {
+                format!("reserved-{x}")
+            };
+
+            (hosts - 1 - x, label)
+        });
+
+        start.chain(end).collect()
+    }
+}
+
+impl std::fmt::Display for ReservePolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ReservePolicyError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn positions_first_and_last() {
+        let resp = ReservePolicy::new(3, 1).positions(254);
+
+        assert_eq!(
+            resp,
+            vec![
+                (0, "gateway".to_string()),
+                (1, "vrrp-1".to_string()),
+                (2, "vrrp-2".to_string()),
+                (253, "monitoring".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn positions_last_in_order() {
+        let resp = ReservePolicy::new(0, 2).positions(6);
+
+        assert_eq!(
+            resp,
+            vec![(4, "reserved-1".to_string()), (5, "monitoring".to_string())]
+        );
+    }
+
+    #[test]
+    fn positions_small_network() {
+        assert_eq!(ReservePolicy::new(3, 2).positions(4).len(), 4);
+        assert_eq!(ReservePolicy::new(1, 1).positions(1).len(), 1);
+        assert!(ReservePolicy::new(1, 1).positions(0).is_empty());
+        assert!(ReservePolicy::default().positions(254).is_empty());
+    }
+
+    #[test]
+    fn policy_with_overrides() {
+        let global = ReservePolicy::new(1, 0);
+
+        assert_eq!(global.with(None, None), Ok(global));
+        assert_eq!(global.with(Some(3), Some(1)), Ok(ReservePolicy::new(3, 1)));
+        assert_eq!(global.with(None, Some(2)), Ok(ReservePolicy::new(1, 2)));
+        assert!(global.with(Some(-1), None).is_err());
+    }
+}
@@ -1,19 +1,30 @@
+use bigdecimal::{BigDecimal, ToPrimitive};
 use ipnet::IpNet;
-use serde::{Deserialize, Serialize};
-
-const MAX_HC: i32 = 0x00FF_FFFF;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use sqlx::{
+    Decode, Encode, Postgres, Type,
+    encode::IsNull,
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef},
+};
+
+const MAX_HC: u128 = u128::MAX;
 const MAX_BITS_LENGTH_HC: u8 = 128;
-const MIN_HC: i32 = 0;
-
-#[derive(Deserialize, Serialize, Debug, Clone, Copy, sqlx::Type, Default)]
-#[sqlx(transparent)]
+const MIN_HC: u128 = 0;
+
+/// Number of hosts of a network.
+///
+/// The value is an `u128`, so the hosts of any ipv4 or ipv6 network can be represented exactly,
+/// the biggest one is an ipv6 `/0` with 2^128 - 2 hosts. In the database it's stored as `NUMERIC`
+/// and in json as a string, the clients parse the numbers as doubles and would lose precision
+#[derive(Debug, Clone, Copy, Default)]
 #[must_use]
-pub struct HostCount(i32);
+pub struct HostCount(u128);
 
 #[derive(Debug, Clone, Copy)]
 pub enum Operation {
-    Add(i32),
-    Sub(i32),
+    Add(u128),
+    Sub(u128),
     Any,
 }
 
@@ -30,7 +41,7 @@ fn validate_u8<T: TryInto<u8>>(
 
 impl HostCount {
     #[must_use]
-    pub fn as_i32(&self) -> i32 {
+    pub fn as_u128(&self) -> u128 {
         self.0
     }
 
@@ -39,7 +50,7 @@ impl HostCount {
     /// Will return `Err` if:
     ///     - The IP's bits are greater then prefix
     ///     - The IP's bits or the prefix's bits are greater than 128
-    ///     - The host's number is greater than `HostCount::MAX`
+    ///     - The operation overflows or underflows the number of hosts
     ///     - The type parameter `T` fails the parse to u8
     pub fn new_with_operation<T>(bits: T, prefix: T, op: Operation) -> Result<Self, HostCountError>
     where
@@ -48,29 +59,27 @@ impl HostCount {
         let bits: u8 = validate_u8(bits, HostCountError::BitsOutRange)?;
         let prefix: u8 = validate_u8(prefix, HostCountError::PrefixOutRange)?;
 
-        2i32.checked_pow(u32::from(
-            bits.checked_sub(prefix)
-                .ok_or(HostCountError::PrefixLTBits)?,
-        ))
-        .ok_or(HostCountError::Overflow)
-        .map(|x| if x > 2 { x - 2 } else { x })
-        .and_then(|x| match op {
-            Operation::Any => (MAX_HC >= x)
-                .then_some(Self(x))
-                .ok_or(HostCountError::Overflow),
-            Operation::Add(n) => x
-                .checked_add(n)
-                .filter(|x| MAX_HC.ge(x))
-                .map(Self)
-                .ok_or(HostCountError::Overflow),
-            Operation::Sub(n) => x
-                .checked_sub(n)
-                .filter(|x| MIN_HC.le(x))
-                .map(Self)
-                .ok_or(HostCountError::Underflow),
-        })
+        let host_bits = bits
+            .checked_sub(prefix)
+            .ok_or(HostCountError::PrefixLTBits)?;
+
+        let hosts = match 1u128.checked_shl(u32::from(host_bits)) {
+            Some(x) if x > 2 => x - 2,
+            Some(x) => x,
+            // The only shift that doesn't fit is an ipv6 /0, that is 2^128 - 2 hosts
+            None => MAX_HC - 1,
+        };
+
+        match op {
+            Operation::Any => Ok(Self(hosts)),
+            Operation::Add(n) => Self(hosts).checked_add(n),
+            Operation::Sub(n) => Self(hosts).checked_sub(n),
+        }
     }
 
+    /// # Errors
+    ///
+    /// Will return `Err` if the operation overflows or underflows the network's hosts
     pub fn new_with_operation_with_ipnet(
         ipnet: IpNet,
         op: Operation,
@@ -84,7 +93,7 @@ impl HostCount {
     }
 
     #[must_use]
-    pub fn new_from_bits_with_sub(bits: u8, prefix: u8, sub: i32) -> Option<Self> {
+    pub fn new_from_bits_with_sub(bits: u8, prefix: u8, sub: u128) -> Option<Self> {
         match Self::new_with_operation(bits, prefix, Operation::Sub(sub)) {
             Ok(e) => Some(e),
             Err(HostCountError::Overflow) => Self::new_max().into(),
@@ -94,7 +103,7 @@ impl HostCount {
     }
 
     #[must_use]
-    pub fn new_from_ipnet_with_sub(ipnet: IpNet, sub: i32) -> Option<Self> {
+    pub fn new_from_ipnet_with_sub(ipnet: IpNet, sub: u128) -> Option<Self> {
         Self::new_from_bits_with_sub(ipnet.max_prefix_len(), ipnet.prefix_len(), sub)
     }
 
@@ -103,7 +112,7 @@ impl HostCount {
     }
 
     #[must_use]
-    pub fn max() -> i32 {
+    pub fn max() -> u128 {
         MAX_HC
     }
 
@@ -111,16 +120,37 @@ impl HostCount {
     pub fn is_max(&self) -> bool {
         self.0 == MAX_HC
     }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the result is greater than `HostCount::max()`
+    pub fn checked_add<T: Into<u128>>(self, rhs: T) -> Result<Self, HostCountError> {
+        self.0
+            .checked_add(rhs.into())
+            .map(Self)
+            .ok_or(HostCountError::Overflow)
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the result is smaller than 0
+    pub fn checked_sub<T: Into<u128>>(self, rhs: T) -> Result<Self, HostCountError> {
+        self.0
+            .checked_sub(rhs.into())
+            .map(Self)
+            .ok_or(HostCountError::Underflow)
+    }
 }
 
-impl TryFrom<i32> for HostCount {
-    type Error = HostCountError;
-    fn try_from(value: i32) -> Result<Self, Self::Error> {
-        match value {
-            ..0 => Err(HostCountError::Underflow),
-            ovr if ovr > MAX_HC + 1 => Err(HostCountError::Overflow),
-            e => Ok(Self(e)),
-        }
+impl From<u128> for HostCount {
+    fn from(value: u128) -> Self {
+        Self(value)
+    }
+}
+
+impl From<HostCount> for u128 {
+    fn from(value: HostCount) -> Self {
+        value.0
     }
 }
 
@@ -136,29 +166,36 @@ impl std::cmp::PartialEq for HostCount {
     }
 }
 
-impl std::cmp::PartialEq<i32> for HostCount {
-    fn eq(&self, other: &i32) -> bool {
+impl std::cmp::PartialEq<u128> for HostCount {
+    fn eq(&self, other: &u128) -> bool {
         self.0.eq(other)
     }
 }
 
+impl std::cmp::Eq for HostCount {}
+
+impl std::cmp::PartialOrd for HostCount {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::cmp::Ord for HostCount {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
 impl std::fmt::Display for HostCount {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
-impl std::ops::Add<i32> for HostCount {
+impl std::ops::Add<u128> for HostCount {
     type Output = HostCount;
-    fn add(self, rhs: i32) -> Self::Output {
-        if rhs < 0 {
-            self - (-rhs)
-        } else {
-            self.0
-                .checked_add(rhs)
-                .filter(|x| MAX_HC.ge(x))
-                .map_or(Self::new_max(), Self)
-        }
+    fn add(self, rhs: u128) -> Self::Output {
+        self.checked_add(rhs).unwrap_or_else(|_| Self::new_max())
     }
 }
 
@@ -169,17 +206,10 @@ impl std::ops::Add for HostCount {
     }
 }
 
-impl std::ops::Sub<i32> for HostCount {
+impl std::ops::Sub<u128> for HostCount {
     type Output = HostCount;
-    fn sub(self, rhs: i32) -> Self::Output {
-        if rhs < 0 {
-            self + (-rhs)
-        } else {
-            self.0
-                .checked_sub(rhs)
-                .filter(|x| 0.le(x))
-                .map_or(Self(MIN_HC), Self)
-        }
+    fn sub(self, rhs: u128) -> Self::Output {
+        self.checked_sub(rhs).unwrap_or(Self(MIN_HC))
     }
 }
 
@@ -190,15 +220,8 @@ impl std::ops::Sub for HostCount {
     }
 }
 
-impl std::ops::Neg for HostCount {
-    type Output = i32;
-    fn neg(self) -> Self::Output {
-        -self.0
-    }
-}
-
-impl std::ops::AddAssign<i32> for HostCount {
-    fn add_assign(&mut self, rhs: i32) {
+impl std::ops::AddAssign<u128> for HostCount {
+    fn add_assign(&mut self, rhs: u128) {
         *self = *self + rhs;
     }
 }
@@ -209,8 +232,8 @@ impl std::ops::AddAssign for HostCount {
     }
 }
 
-impl std::ops::SubAssign<i32> for HostCount {
-    fn sub_assign(&mut self, rhs: i32) {
+impl std::ops::SubAssign<u128> for HostCount {
+    fn sub_assign(&mut self, rhs: u128) {
         *self = *self - rhs;
     }
 }
@@ -221,6 +244,62 @@ impl std::ops::SubAssign for HostCount {
     }
 }
 
+impl Serialize for HostCount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+/// Reads the string written by `Serialize`, a number is also accepted
+impl<'de> Deserialize<'de> for HostCount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HostCountVisitor;
+
+        impl de::Visitor<'_> for HostCountVisitor {
+            type Value = HostCount;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a number of hosts between {MIN_HC} and {MAX_HC}")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(HostCount(v.into()))
+            }
+
+            fn visit_u128<E: de::Error>(self, v: u128) -> Result<Self::Value, E> {
+                Ok(HostCount(v))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map(HostCount).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(HostCountVisitor)
+    }
+}
+
+impl Type<Postgres> for HostCount {
+    fn type_info() -> PgTypeInfo {
+        <BigDecimal as Type<Postgres>>::type_info()
+    }
+}
+
+impl Encode<'_, Postgres> for HostCount {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <BigDecimal as Encode<'_, Postgres>>::encode(BigDecimal::from(self.0), buf)
+    }
+}
+
+impl Decode<'_, Postgres> for HostCount {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        <BigDecimal as Decode<'_, Postgres>>::decode(value)?
+            .to_u128()
+            .map(Self)
+            .ok_or_else(|| HostCountError::ParseOutOfRange.into())
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum HostCountError {
     ParseOutOfRange,
@@ -254,89 +333,118 @@ impl std::fmt::Display for HostCountError {
 
 #[cfg(test)]
 mod test {
-    use crate::types::host_count::{HostCount, HostCountError};
+    use crate::types::host_count::{HostCount, HostCountError, Operation};
     use ipnet::IpNet;
 
     #[test]
     fn host_counter_instance_from_prefix() {
         let ipnet = "172.30.0.0/24".parse::<IpNet>().unwrap();
         let pref = HostCount::new(ipnet.max_prefix_len(), ipnet.prefix_len()).unwrap();
-        assert_eq!(pref.as_i32(), 254);
+        assert_eq!(pref.as_u128(), 254);
     }
 
     #[test]
     fn host_counter_instance_from_prefix_31() {
         let ipnet = "172.30.0.0/31".parse::<IpNet>().unwrap();
         let pref = HostCount::new(ipnet.max_prefix_len(), ipnet.prefix_len()).unwrap();
-        assert_eq!(pref.as_i32(), 2);
+        assert_eq!(pref.as_u128(), 2);
     }
 
     #[test]
     fn host_counter_instance_from_prefix_32() {
         let ipnet = "172.30.0.0/32".parse::<IpNet>().unwrap();
         let pref = HostCount::new(ipnet.max_prefix_len(), ipnet.prefix_len()).unwrap();
-        assert_eq!(pref.as_i32(), 1);
+        assert_eq!(pref.as_u128(), 1);
     }
 
     #[test]
-    fn host_counter_instance_from_i32() {
-        assert_eq!(HostCount::try_from(10), Ok(HostCount(10)));
+    fn host_counter_instance_from_prefix_7() {
+        let ipnet = "10.0.0.0/7".parse::<IpNet>().unwrap();
+        assert_eq!(HostCount::from(ipnet), (1u128 << 25) - 2);
     }
 
     #[test]
-    fn host_counter_addition_overflow() {
-        let pref: HostCount = HostCount::max().try_into().unwrap();
-        assert_eq!(pref.as_i32(), HostCount::max());
-        assert_eq!(HostCount::max(), pref.as_i32());
+    fn host_counter_instance_from_ipv6() {
+        let ipnet = "2001:db8::/64".parse::<IpNet>().unwrap();
+        assert_eq!(HostCount::from(ipnet), (1u128 << 64) - 2);
+
+        let ipnet = "::/0".parse::<IpNet>().unwrap();
+        assert_eq!(HostCount::from(ipnet), u128::MAX - 1);
     }
 
     #[test]
-    fn host_counter_add_hostcount() {
+    fn host_counter_instance_from_u128() {
+        assert_eq!(HostCount::from(10), HostCount(10));
+    }
+
+    #[test]
+    fn host_counter_json_string() {
+        let max = HostCount::new_max();
+        let json = serde_json::to_string(&max).unwrap();
+
+        assert_eq!(json, format!("\"{}\"", u128::MAX));
+        assert_eq!(serde_json::from_str::<HostCount>(&json).unwrap(), max);
+        assert_eq!(serde_json::from_str::<HostCount>("10").unwrap(), 10);
+        assert!(serde_json::from_str::<HostCount>("-1").is_err());
+    }
+
+    #[test]
+    fn host_counter_addition_overflow() {
         let pref = HostCount::new_max();
-        let pref = pref + (-HostCount::new_max());
-        assert_ne!(pref, HostCount::new_max());
-        assert_eq!(pref, HostCount(0));
+        assert_eq!(pref.checked_add(1u128), Err(HostCountError::Overflow));
+        assert_eq!(pref + 1, HostCount::new_max());
     }
 
     #[test]
-    fn host_counter_sub_hostcount_neg() {
-        let pref = HostCount(0);
-        let pref = pref - (-HostCount::new_max());
-        assert_eq!(pref, HostCount::new_max());
-        assert_ne!(pref, HostCount(0));
+    fn host_counter_operation_add_overflow() {
+        let resp = HostCount::new_with_operation(128, 0, Operation::Add(2));
+        assert_eq!(resp, Err(HostCountError::Overflow));
+    }
+
+    #[test]
+    fn host_counter_operation_sub_underflow() {
+        let resp = HostCount::new_with_operation(32, 24, Operation::Sub(255));
+        assert_eq!(resp, Err(HostCountError::Underflow));
     }
 
-    fn host_counter_sub_hostcount_() {
-        let pref = HostCount(500000);
+    #[test]
+    fn host_counter_operation_sub_ipv6() {
+        let resp = HostCount::new_with_operation(128, 48, Operation::Sub(65_536));
+        assert_eq!(resp, Ok(HostCount((1u128 << 80) - 2 - 65_536)));
+    }
+
+    #[test]
+    fn host_counter_sub_hostcount() {
+        let pref = HostCount(500_000);
         let pref = pref - HostCount::new_max();
         assert_ne!(pref, HostCount::new_max());
         assert_eq!(pref, HostCount(0));
     }
 
     #[test]
-    fn host_counter_sub_i32() {
+    fn host_counter_add_hostcount() {
         let pref = HostCount(0);
-        let pref = pref - (-HostCount::max());
+        let pref = pref + HostCount::new_max();
         assert_eq!(pref, HostCount::new_max());
         assert_ne!(pref, HostCount(0));
     }
 
     #[test]
-    fn host_counter_sub_assign_i32() {
+    fn host_counter_sub_assign_u128() {
         let mut pref = HostCount(0);
         pref -= 500;
         assert_eq!(pref, HostCount(0));
     }
 
     #[test]
-    fn host_counter_sub_assign_i32_two() {
+    fn host_counter_sub_assign_u128_two() {
         let mut pref = HostCount(500);
         pref -= 500;
         assert_eq!(pref, HostCount(0));
     }
 
     #[test]
-    fn host_counter_add_assign_i32() {
+    fn host_counter_add_assign_u128() {
         let mut pref = HostCount(4000);
         pref += HostCount::max();
         assert_eq!(pref, HostCount::new_max());